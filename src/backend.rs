@@ -0,0 +1,77 @@
+//! The SQL library the repos run on.
+//!
+//! By default the DH database is attached through duckdb's sqlite extension.
+//! The `sqlite` feature opens it directly with rusqlite instead, which needs no extension download
+//! and returns the columns with their sqlite types instead of as varchar.
+//!
+//! duckdb-rs mirrors the rusqlite API, so both are re-exported under the same names
+//! and only the few places where they differ are wrapped here.
+
+#[cfg(not(any(feature = "duckdb", feature = "sqlite")))]
+compile_error!("enable either the `duckdb` or the `sqlite` feature");
+
+#[cfg(all(feature = "duckdb", not(feature = "sqlite")))]
+pub use duckdb::{CachedStatement, Connection, Error, Result, Row, Rows, Statement, ToSql, types};
+#[cfg(feature = "sqlite")]
+pub use rusqlite::{
+    CachedStatement, Connection, Error, Result, Row, Rows, Statement, ToSql, types,
+};
+
+/// `LastModifiedUnixDateTime` as an integer, duckdb reads every sqlite column as varchar.
+#[cfg(all(feature = "duckdb", not(feature = "sqlite")))]
+pub const LAST_MODIFIED: &str = "CAST(LastModifiedUnixDateTime AS BIGINT)";
+#[cfg(feature = "sqlite")]
+pub const LAST_MODIFIED: &str = "LastModifiedUnixDateTime";
+
+/// Runs a select whose parameters are bound and returns its rows.
+#[inline]
+pub fn query<'s>(stmt: &'s mut Statement<'_>) -> Result<Rows<'s>> {
+    // duckdb only fetches the rows of an executed statement, rusqlite executes on the first step
+    #[cfg(all(feature = "duckdb", not(feature = "sqlite")))]
+    stmt.raw_execute()?;
+
+    Ok(stmt.raw_query())
+}
+
+/// Column names of `table` in the attached database, empty if the table doesn't exist.
+#[inline]
+pub fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    #[cfg(all(feature = "duckdb", not(feature = "sqlite")))]
+    const SQL: &str = "SELECT column_name FROM information_schema.columns \
+                       WHERE table_catalog = 'dh' AND table_name = ? ORDER BY ordinal_position";
+    #[cfg(feature = "sqlite")]
+    const SQL: &str = "SELECT name FROM pragma_table_info(?) ORDER BY cid";
+
+    let mut stmt = conn.prepare_cached(SQL)?;
+    stmt.raw_bind_parameter(1, table)?;
+    let mut rows = query(&mut stmt)?;
+
+    let mut columns = Vec::new();
+    while let Some(row) = rows.next()? {
+        columns.push(row.get(0)?);
+    }
+    Ok(columns)
+}
+
+/// Whether [`vacuum`] can rebuild the database file, duckdb can't vacuum an attached sqlite database.
+pub const CAN_VACUUM: bool = cfg!(feature = "sqlite");
+
+/// Rebuilds the database file to give back the space of deleted rows.
+///
+/// Fails without [`CAN_VACUUM`], check it before deleting anything.
+#[inline]
+pub fn vacuum(conn: &Connection) -> crate::Result<()> {
+    #[cfg(all(feature = "duckdb", not(feature = "sqlite")))]
+    {
+        let _ = conn;
+        Err(crate::Error::Io(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "duckdb can't vacuum an attached sqlite database",
+        )))
+    }
+    #[cfg(feature = "sqlite")]
+    {
+        conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use crate::backend::Row;
+
+use crate::repo::{All, Query, Repo, WritableRepo};
+
+/// A beacon beam as tracked by DH, stored next to the LoD data.
+///
+/// <https://gitlab.com/distant-horizons-team/distant-horizons-core/-/blob/main/core/src/main/java/com/seibel/distanthorizons/core/sql/repo/BeaconBeamRepo.java>
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
+pub struct BeaconBeam {
+    // BlockPosX
+    pub x: i32,
+    // BlockPosY
+    pub y: i32,
+    // BlockPosZ
+    pub z: i32,
+    // ColorR, ColorG, ColorB
+    pub color: (u8, u8, u8),
+    // LastModifiedUnixDateTime
+    last_modified: i64,
+    // CreatedUnixDateTime
+    created: i64,
+}
+
+impl BeaconBeam {
+    #[inline]
+    #[must_use]
+    pub const fn last_modified(&self) -> i64 {
+        self.last_modified
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn created(&self) -> i64 {
+        self.created
+    }
+
+    #[inline]
+    pub fn get_all(conn: &crate::backend::Connection) -> Result<Vec<Self>, crate::backend::Error> {
+        let q = All.ordered("BlockPosX, BlockPosZ, BlockPosY");
+
+        Self::select_vec(conn, &q, |b| b)
+    }
+}
+
+impl Repo for BeaconBeam {
+    const TABLE: &'static str = "BeaconBeam";
+
+    type Element<'r> = BeaconBeam;
+
+    #[inline]
+    fn from_row<'r>(row: &'r Row) -> crate::backend::Result<Self::Element<'r>> {
+        Ok(BeaconBeam {
+            x: row.get("BlockPosX")?,
+            y: row.get("BlockPosY")?,
+            z: row.get("BlockPosZ")?,
+            color: (row.get("ColorR")?, row.get("ColorG")?, row.get("ColorB")?),
+            last_modified: row.get("LastModifiedUnixDateTime")?,
+            created: row.get("CreatedUnixDateTime")?,
+        })
+    }
+}
+
+impl WritableRepo for BeaconBeam {
+    const INSERT: &'static str = "
+            (
+                BlockPosX,
+                BlockPosY,
+                BlockPosZ,
+                ColorR,
+                ColorG,
+                ColorB,
+                LastModifiedUnixDateTime,
+                CreatedUnixDateTime
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ";
+
+    type Key = (i32, i32, i32);
+
+    #[inline]
+    fn key(beam: &Self::Element<'_>) -> Self::Key {
+        (beam.x, beam.y, beam.z)
+    }
+
+    #[inline]
+    fn by_key() -> impl Query<Self::Key> {
+        struct Q;
+
+        impl Query<(i32, i32, i32)> for Q {
+            fn r#where(&self) -> &str {
+                "BlockPosX = ? AND BlockPosY = ? AND BlockPosZ = ?"
+            }
+
+            fn bind_params(
+                &self,
+                stmt: &mut crate::backend::Statement,
+                (x, y, z): (i32, i32, i32),
+            ) -> crate::backend::Result<()> {
+                stmt.raw_bind_parameter(1, x)?;
+                stmt.raw_bind_parameter(2, y)?;
+                stmt.raw_bind_parameter(3, z)?;
+                Ok(())
+            }
+        }
+
+        Q
+    }
+
+    #[inline]
+    fn bind_insert(
+        stmt: &mut crate::backend::Statement,
+        beam: Self::Element<'_>,
+    ) -> crate::backend::Result<()> {
+        let (r, g, b) = beam.color;
+        stmt.raw_bind_parameter(1, beam.x)?;
+        stmt.raw_bind_parameter(2, beam.y)?;
+        stmt.raw_bind_parameter(3, beam.z)?;
+        stmt.raw_bind_parameter(4, r)?;
+        stmt.raw_bind_parameter(5, g)?;
+        stmt.raw_bind_parameter(6, b)?;
+        stmt.raw_bind_parameter(7, beam.last_modified)?;
+        stmt.raw_bind_parameter(8, beam.created)?;
+        debug_assert_eq!(stmt.parameter_count(), 8);
+
+        Ok(())
+    }
+}
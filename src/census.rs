@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use smol_str::SmolStr;
+
+use crate::{DetailLevel, Result, Section, block::Block};
+
+/// Area and volume in blocks covered by a block or biome.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Count {
+    /// Columns where it is the topmost non-air data point, in square blocks
+    pub surface: u64,
+    /// Non-air data points, in cubic blocks
+    pub volume: u64,
+}
+
+impl core::ops::AddAssign for Count {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        self.surface += rhs.surface;
+        self.volume += rhs.volume;
+    }
+}
+
+/// Block and biome counts over all sections of one detail level.
+#[derive(Debug, Clone, Default)]
+pub struct Census {
+    pub sections: usize,
+    /// Sections that failed to decompress and are not counted
+    pub failed: usize,
+    pub blocks: HashMap<SmolStr, Count>,
+    pub biomes: HashMap<SmolStr, Count>,
+}
+
+impl Census {
+    /// Decompresses and counts every section of `detail_level`, one at a time.
+    pub fn collect(conn: &crate::backend::Connection, detail_level: DetailLevel) -> Result<Self> {
+        let mut census = Self::default();
+
+        Section::for_each(conn, Some(detail_level), |mut section| {
+            if section.decompress().is_err() {
+                census.failed += 1;
+            } else {
+                census.add(&section);
+            }
+            Ok::<_, crate::Error>(())
+        })?;
+
+        Ok(census)
+    }
+
+    /// Counts a decompressed section, compressed sections are skipped.
+    #[inline]
+    pub fn add(&mut self, section: &Section) {
+        let (Some(cols), Some(mapping)) = (section.column_data(), section.mapping()) else {
+            return;
+        };
+        let width = section.width() as u64;
+        let column_area = width * width;
+
+        // Count by mapping index first, there are far fewer entries than data points
+        let mut counts = vec![Count::default(); mapping.len()];
+        for col in cols.as_ref() {
+            let mut surface = true;
+            for point in col.iter() {
+                let Some(count) = counts.get_mut(point.id() as usize) else {
+                    continue;
+                };
+                if mapping[point].is_air() {
+                    continue;
+                }
+                if surface {
+                    count.surface += column_area;
+                    surface = false;
+                }
+                count.volume += u64::from(point.height()) * column_area;
+            }
+        }
+
+        for (entry, count) in mapping.iter().zip(counts) {
+            if count == Count::default() {
+                continue;
+            }
+            *self
+                .blocks
+                .entry(entry.full_identifier().into())
+                .or_default() += count;
+            *self.biomes.entry(entry.biome().into()).or_default() += count;
+        }
+        self.sections += 1;
+    }
+
+    /// Total surface of all counted columns.
+    #[inline]
+    #[must_use]
+    pub fn surface(&self) -> u64 {
+        self.biomes.values().map(|c| c.surface).sum()
+    }
+
+    /// Blocks sorted by surface, then volume, largest first.
+    #[inline]
+    #[must_use]
+    pub fn sorted_blocks(&self) -> Vec<(&str, Count)> {
+        sorted(&self.blocks)
+    }
+
+    /// Biomes sorted by surface, then volume, largest first.
+    #[inline]
+    #[must_use]
+    pub fn sorted_biomes(&self) -> Vec<(&str, Count)> {
+        sorted(&self.biomes)
+    }
+}
+
+fn sorted(counts: &HashMap<SmolStr, Count>) -> Vec<(&str, Count)> {
+    let mut sorted: Vec<_> = counts.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+    sorted.sort_unstable_by(|(a_name, a), (b_name, b)| {
+        (b.surface, b.volume)
+            .cmp(&(a.surface, a.volume))
+            .then_with(|| a_name.cmp(b_name))
+    });
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::section::{
+        builder::{Run, SectionBuilder},
+        mapping::Entry,
+        pos::Pos,
+    };
+
+    fn run(biome: &str, block: &str, y: core::ops::Range<i32>) -> Run {
+        Run::new(Entry::new(biome, block), y)
+    }
+
+    /// A Chunk8 section, its columns are 2x2 blocks.
+    fn section() -> Section<'static> {
+        let mut builder = SectionBuilder::new(Pos::new(DetailLevel::Chunk8, -1, 3));
+        // Air above the surface is stored too
+        builder
+            .push(0, 0, run("minecraft:plains", "minecraft:air", 5..20))
+            .unwrap()
+            .push(0, 0, run("minecraft:plains", "minecraft:grass_block", 4..5))
+            .unwrap()
+            .push(0, 0, run("minecraft:plains", "minecraft:dirt", -64..4))
+            .unwrap();
+        builder
+            .push(1, 0, run("minecraft:desert", "minecraft:sand", -64..0))
+            .unwrap();
+        builder
+            .push(0, 1, run("minecraft:plains", "minecraft:air", -64..100))
+            .unwrap();
+        builder.build().unwrap()
+    }
+
+    const fn count(surface: u64, volume: u64) -> Count {
+        Count { surface, volume }
+    }
+
+    #[test]
+    fn surface_and_volume() {
+        let mut census = Census::default();
+        census.add(&section());
+
+        assert_eq!(census.sections, 1);
+        assert_eq!(
+            census.sorted_blocks(),
+            [
+                ("minecraft:sand", count(4, 64 * 4)),
+                ("minecraft:grass_block", count(4, 4)),
+                ("minecraft:dirt", count(0, 68 * 4)),
+            ]
+        );
+        assert_eq!(
+            census.sorted_biomes(),
+            [
+                ("minecraft:plains", count(4, 69 * 4)),
+                ("minecraft:desert", count(4, 64 * 4)),
+            ]
+        );
+        // The column of only air has no surface
+        assert_eq!(census.surface(), 8);
+
+        census.add(&section());
+        assert_eq!(census.sections, 2);
+        assert_eq!(census.blocks["minecraft:dirt"], count(0, 2 * 68 * 4));
+        assert_eq!(census.surface(), 16);
+    }
+
+    #[test]
+    fn compressed_sections_are_skipped() {
+        let mut section = section();
+        section.compress().unwrap();
+        section.drop_decompressed();
+
+        let mut census = Census::default();
+        census.add(&section);
+        assert_eq!(census.sections, 0);
+        assert!(census.blocks.is_empty());
+    }
+}
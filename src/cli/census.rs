@@ -0,0 +1,86 @@
+use anyhow::Result;
+use distant_horizons::backend::Connection;
+use serde_json::{Value, json};
+
+use distant_horizons::{
+    census::{Census, Count},
+    section::pos::Pos,
+};
+
+use super::Options;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Print comma separated values instead of a table
+    #[arg(long)]
+    csv: bool,
+
+    /// Only print the first entries of each table
+    #[arg(long)]
+    limit: Option<usize>,
+}
+
+pub fn run(conn: &Connection, options: Options, args: Args) -> Result<()> {
+    let detail_level = options
+        .detail_level
+        .unwrap_or(Pos::SECTION_MINIMUM_DETAIL_LEVEL);
+    let census = Census::collect(conn, detail_level)?;
+    let limit = args.limit.unwrap_or(usize::MAX);
+
+    let tables = [
+        ("block", census.sorted_blocks()),
+        ("biome", census.sorted_biomes()),
+    ];
+
+    if options.json {
+        let list = |rows: &[(&str, Count)]| -> Vec<Value> {
+            rows.iter()
+                .take(limit)
+                .map(|(name, c)| json!({ "name": name, "surface": c.surface, "volume": c.volume }))
+                .collect()
+        };
+        println!(
+            "{}",
+            json!({
+                "sections": census.sections,
+                "failed": census.failed,
+                "surface": census.surface(),
+                "blocks": list(&tables[0].1),
+                "biomes": list(&tables[1].1),
+            })
+        );
+        return Ok(());
+    }
+
+    if args.csv {
+        println!("kind,name,surface,volume");
+        for (kind, rows) in &tables {
+            for (name, c) in rows.iter().take(limit) {
+                println!("{kind},{name},{},{}", c.surface, c.volume);
+            }
+        }
+        return Ok(());
+    }
+
+    let total = census.surface().max(1) as f64;
+    println!(
+        "{} sections at {detail_level:?}, {} failed, {} square blocks of surface",
+        census.sections,
+        census.failed,
+        census.surface(),
+    );
+    for (kind, rows) in &tables {
+        println!();
+        println!("{kind:<48} {:>14} {:>7} {:>16}", "surface", "%", "volume");
+        for (name, c) in rows.iter().take(limit) {
+            println!(
+                "{name:<48} {:>14} {:>6.2}% {:>16}",
+                c.surface,
+                c.surface as f64 * 100. / total,
+                c.volume,
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use distant_horizons::backend::Connection;
+use serde_json::{Value, json};
+
+use distant_horizons::{
+    DetailLevel, Section,
+    diff::{self, ColumnChange, SectionDiff},
+};
+
+use super::{Options, pos_json};
+
+/// Longest side of the difference image in pixels.
+const MAX_IMAGE_SIZE: usize = 16384;
+
+const ADDED: [u8; 4] = [0, 200, 0, 255];
+const REMOVED: [u8; 4] = [200, 0, 0, 255];
+const BLOCK_CHANGED: [u8; 4] = [255, 200, 0, 255];
+const HEIGHT_CHANGED: [u8; 4] = [0, 120, 255, 255];
+const OTHER_CHANGED: [u8; 4] = [200, 0, 200, 255];
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Newer database or backup to compare against
+    pub other: PathBuf,
+
+    /// List every changed column
+    #[arg(long)]
+    columns: bool,
+
+    /// Draw the differences of one detail level (default `Chunk16`) into a PNG
+    #[arg(long)]
+    image: Option<PathBuf>,
+}
+
+fn column_json(change: &ColumnChange) -> Value {
+    let surface = |s: &Option<diff::Surface>| {
+        s.as_ref()
+            .map_or(Value::Null, |s| json!({ "block": s.block, "y": s.y }))
+    };
+    json!({
+        "x": change.dx,
+        "z": change.dz,
+        "before": surface(&change.before),
+        "after": surface(&change.after),
+    })
+}
+
+fn describe(surface: Option<&diff::Surface>) -> String {
+    surface.map_or_else(
+        || "nothing".to_string(),
+        |s| format!("{} at y {}", s.block, s.y),
+    )
+}
+
+pub fn run(before: &Connection, after: &Connection, options: Options, args: Args) -> Result<()> {
+    let diffs = diff::diff(before, after, options.detail_level)?;
+
+    for d in &diffs {
+        let pos = d.pos();
+        if options.json {
+            let mut value = pos_json(pos);
+            match d {
+                SectionDiff::Added(_) => value["change"] = "added".into(),
+                SectionDiff::Removed(_) => value["change"] = "removed".into(),
+                SectionDiff::Changed(_, columns) => {
+                    value["change"] = "changed".into();
+                    value["columns_changed"] = columns.len().into();
+                    if args.columns {
+                        value["columns"] = columns.iter().map(column_json).collect();
+                    }
+                }
+                SectionDiff::Unreadable(_) => value["change"] = "unreadable".into(),
+            }
+            println!("{value}");
+            continue;
+        }
+
+        match d {
+            SectionDiff::Added(_) => println!("+ {pos}"),
+            SectionDiff::Removed(_) => println!("- {pos}"),
+            SectionDiff::Changed(_, columns) => {
+                let blocks = columns.iter().filter(|c| c.block_changed()).count();
+                let heights = columns.iter().filter(|c| c.height_changed()).count();
+                println!(
+                    "~ {pos}: {} columns changed, {blocks} surface blocks, {heights} heights",
+                    columns.len()
+                );
+                if args.columns {
+                    for c in columns {
+                        println!(
+                            "    {},{}: {} -> {}",
+                            c.dx,
+                            c.dz,
+                            describe(c.before.as_ref()),
+                            describe(c.after.as_ref()),
+                        );
+                    }
+                }
+            }
+            SectionDiff::Unreadable(_) => println!("? {pos}: changed, but unreadable"),
+        }
+    }
+
+    if !options.json {
+        let count = |f: fn(&SectionDiff) -> bool| diffs.iter().filter(|d| f(d)).count();
+        println!(
+            "{} added, {} removed, {} changed, {} unreadable",
+            count(|d| matches!(d, SectionDiff::Added(_))),
+            count(|d| matches!(d, SectionDiff::Removed(_))),
+            count(|d| matches!(d, SectionDiff::Changed(..))),
+            count(|d| matches!(d, SectionDiff::Unreadable(_))),
+        );
+    }
+
+    if let Some(path) = &args.image {
+        let detail_level = options.detail_level.unwrap_or(DetailLevel::Chunk16);
+        write_image(&diffs, detail_level, path)?;
+    }
+
+    Ok(())
+}
+
+/// One pixel per column, unchanged areas stay transparent.
+fn write_image(diffs: &[SectionDiff], detail_level: DetailLevel, path: &Path) -> Result<()> {
+    let diffs: Vec<_> = diffs
+        .iter()
+        .filter(|d| d.pos().detail_level == detail_level)
+        .collect();
+    anyhow::ensure!(!diffs.is_empty(), "no differences at {detail_level:?}");
+
+    let min_x = diffs.iter().map(|d| d.pos().x).min().unwrap_or_default();
+    let max_x = diffs.iter().map(|d| d.pos().x).max().unwrap_or_default();
+    let min_z = diffs.iter().map(|d| d.pos().z).min().unwrap_or_default();
+    let max_z = diffs.iter().map(|d| d.pos().z).max().unwrap_or_default();
+
+    let width = (max_x - min_x + 1) as usize * Section::WIDTH;
+    let height = (max_z - min_z + 1) as usize * Section::WIDTH;
+    anyhow::ensure!(
+        width <= MAX_IMAGE_SIZE && height <= MAX_IMAGE_SIZE,
+        "{width}x{height} pixels is too large, pick a coarser detail level"
+    );
+
+    let mut image = vec![0; width * height * 4];
+    for d in diffs {
+        let left = (d.pos().x - min_x) as usize * Section::WIDTH;
+        let top = (d.pos().z - min_z) as usize * Section::WIDTH;
+        let mut set = |dx: usize, dz: usize, color: [u8; 4]| {
+            let offset = ((top + dz) * width + left + dx) * 4;
+            image[offset..offset + 4].copy_from_slice(&color);
+        };
+
+        match d {
+            SectionDiff::Added(_) | SectionDiff::Removed(_) | SectionDiff::Unreadable(_) => {
+                let color = match d {
+                    SectionDiff::Added(_) => ADDED,
+                    SectionDiff::Removed(_) => REMOVED,
+                    _ => OTHER_CHANGED,
+                };
+                for dz in 0..Section::WIDTH {
+                    for dx in 0..Section::WIDTH {
+                        set(dx, dz, color);
+                    }
+                }
+            }
+            SectionDiff::Changed(_, columns) => {
+                for c in columns {
+                    let color = if c.block_changed() {
+                        BLOCK_CHANGED
+                    } else if c.height_changed() {
+                        HEIGHT_CHANGED
+                    } else {
+                        OTHER_CHANGED
+                    };
+                    set(c.dx, c.dz, color);
+                }
+            }
+        }
+    }
+
+    let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&image)?;
+
+    Ok(())
+}
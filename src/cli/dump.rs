@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use distant_horizons::backend::Connection;
+use serde_json::{Value, json};
+
+use distant_horizons::{Section, block::Block, section::pos::Pos};
+
+use super::{Options, entry_json, pos_json};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Section position as `<detail level>*<x>,<z>`
+    pos: Pos,
+
+    /// Only print the column at `x,z`, relative to the section (0..64)
+    #[arg(long, value_parser = parse_column)]
+    column: Option<(usize, usize)>,
+}
+
+fn parse_column(s: &str) -> Result<(usize, usize)> {
+    let (x, z) = s.split_once(',').context("expected <x>,<z>")?;
+    let (x, z): (usize, usize) = (x.trim().parse()?, z.trim().parse()?);
+    anyhow::ensure!(
+        x < Section::WIDTH && z < Section::WIDTH,
+        "column out of range"
+    );
+    Ok((x, z))
+}
+
+pub fn run(conn: &Connection, options: Options, args: Args) -> Result<()> {
+    let mut section =
+        Section::get(conn, args.pos)?.with_context(|| format!("no section at {}", args.pos))?;
+    section.decompress()?;
+
+    let cols = section.column_data().context("missing column data")?;
+    let mapping = section.mapping().context("missing mapping")?;
+
+    let columns = (0..Section::WIDTH)
+        .flat_map(|dx| (0..Section::WIDTH).map(move |dz| (dx, dz)))
+        .filter(|col| args.column.is_none_or(|c| c == *col));
+
+    if options.json {
+        let columns: Vec<Value> = columns
+            .map(|(dx, dz)| {
+                let points: Vec<Value> = cols
+                    .get(dx, dz)
+                    .iter()
+                    .map(|p| {
+                        json!({
+                            "id": p.id(),
+                            "min_y": section.min_y + i32::from(p.min_y()),
+                            "height": p.height(),
+                            "sky_light": p.sky_light() as u8,
+                            "block_light": p.block_light() as u8,
+                        })
+                    })
+                    .collect();
+                json!({ "x": dx, "z": dz, "points": points })
+            })
+            .collect();
+
+        let mut value = pos_json(section.pos);
+        value["min_y"] = section.min_y.into();
+        value["compression"] = format!("{:?}", section.compression()).into();
+        value["format_version"] = section.format_version().into();
+        value["last_modified"] = section.last_modified().into();
+        value["created"] = section.created().into();
+        value["mapping"] = mapping.iter().map(entry_json).collect();
+        value["columns"] = columns.into();
+        println!("{value}");
+        return Ok(());
+    }
+
+    println!(
+        "{} at x {}, z {}, {} blocks wide",
+        section.pos,
+        section.pos.min_x(),
+        section.pos.min_z(),
+        section.block_width(),
+    );
+    println!(
+        "min_y {}, {:?}, format version {}, last modified {}, created {}",
+        section.min_y,
+        section.compression(),
+        section.format_version(),
+        section.last_modified(),
+        section.created(),
+    );
+
+    for (dx, dz) in columns {
+        let col = &cols.get(dx, dz);
+        if col.is_empty() {
+            continue;
+        }
+        println!("column {dx},{dz}");
+        for point in col.iter() {
+            let entry = &mapping[point];
+            let min_y = section.min_y + i32::from(point.min_y());
+            let states: Vec<String> = entry.states().map(|(k, v)| format!("{k}={v}")).collect();
+            println!(
+                "    y {min_y}..{}\t{}[{}]\t{}\tlight {}/{}",
+                min_y + i32::from(point.height()),
+                entry.full_identifier(),
+                states.join(","),
+                entry.biome(),
+                point.sky_light() as u8,
+                point.block_light() as u8,
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use distant_horizons::backend::Connection;
+use serde_json::Value;
+
+use distant_horizons::Section;
+
+use super::{Options, entry_json, pos_json};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// File to write, defaults to stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// One JSON object per section. Columns are listed in DH's order, x major,
+/// every data point as `[mapping index, min_y, height, sky light, block light]`.
+fn section_json(section: &Section) -> Result<Value> {
+    let cols = section.column_data().context("missing column data")?;
+    let mapping = section.mapping().context("missing mapping")?;
+
+    let columns: Vec<Value> = cols
+        .as_ref()
+        .iter()
+        .map(|col| {
+            col.iter()
+                .map(|p| {
+                    Value::from(vec![
+                        p.id(),
+                        u32::from(p.min_y()),
+                        u32::from(p.height()),
+                        p.sky_light() as u32,
+                        p.block_light() as u32,
+                    ])
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut value = pos_json(section.pos);
+    value["min_y"] = section.min_y.into();
+    value["last_modified"] = section.last_modified().into();
+    value["created"] = section.created().into();
+    value["mapping"] = mapping.iter().map(entry_json).collect();
+    value["columns"] = columns.into();
+    Ok(value)
+}
+
+pub fn run(conn: &Connection, options: Options, args: Args) -> Result<()> {
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).with_context(|| format!("creating {}", path.display()))?,
+        )),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    let mut exported = 0;
+    Section::for_each(conn, options.detail_level, |mut section| {
+        if let Err(e) = section.decompress() {
+            eprintln!("Skipping section {}: {e:#}", section.pos);
+            return Ok(());
+        }
+        writeln!(out, "{}", section_json(&section)?)?;
+        exported += 1;
+        Ok::<_, anyhow::Error>(())
+    })?;
+    out.flush()?;
+
+    if let Some(path) = &args.output {
+        eprintln!("Exported {exported} sections to {}", path.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use anyhow::Result;
+use distant_horizons::backend::Connection;
+use serde_json::json;
+
+use distant_horizons::find::{self, Pattern};
+
+use super::Options;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Block to search for, `minecraft:chest` or with states `chest[type=single]`
+    block: Option<String>,
+
+    /// Only match blocks in this biome, or search for the biome alone
+    #[arg(long)]
+    biome: Option<String>,
+
+    /// Hits at most this many blocks apart form one location
+    #[arg(long, default_value_t = 16)]
+    radius: i32,
+
+    /// Only print the first locations
+    #[arg(long)]
+    limit: Option<usize>,
+}
+
+pub fn run(conn: &Connection, options: Options, args: Args) -> Result<()> {
+    let mut pattern = match &args.block {
+        Some(block) => Pattern::block(block)?,
+        None => Pattern::default(),
+    };
+    if let Some(biome) = &args.biome {
+        pattern = pattern.with_biome(biome);
+    }
+    anyhow::ensure!(!pattern.is_empty(), "give a block, a biome or both");
+
+    let hits = find::find(conn, &pattern, options.detail_level)?;
+    let locations = find::cluster(&hits, args.radius);
+    let limit = args.limit.unwrap_or(usize::MAX);
+
+    for location in locations.iter().take(limit) {
+        let (x, y, z) = location.center();
+        if options.json {
+            println!(
+                "{}",
+                json!({
+                    "center": { "x": x, "y": y, "z": z },
+                    "min": { "x": location.min.0, "y": location.min.1, "z": location.min.2 },
+                    "max": { "x": location.max.0, "y": location.max.1, "z": location.max.2 },
+                    "hits": location.hits,
+                })
+            );
+        } else {
+            println!(
+                "{x} {y} {z}\t{} hits\tx {}..={}, y {}..={}, z {}..={}",
+                location.hits,
+                location.min.0,
+                location.max.0,
+                location.min.1,
+                location.max.1,
+                location.min.2,
+                location.max.2,
+            );
+        }
+    }
+
+    if !options.json {
+        println!("{} hits in {} locations", hits.len(), locations.len());
+    }
+
+    Ok(())
+}
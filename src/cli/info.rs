@@ -0,0 +1,150 @@
+use anyhow::Result;
+use distant_horizons::backend::Connection;
+use serde_json::{Value, json};
+
+use distant_horizons::{
+    schema::{self, Schema},
+    stats::{Area, LevelStats, Stats},
+};
+
+use super::Options;
+
+fn area_json(area: Option<Area>) -> Value {
+    area.map_or(Value::Null, |a| {
+        json!({
+            "min": { "x": a.min_x, "z": a.min_z },
+            "max": { "x": a.max_x, "z": a.max_z },
+        })
+    })
+}
+
+fn level_json(level: &LevelStats) -> Value {
+    let compression: serde_json::Map<_, _> = level
+        .compression
+        .iter()
+        .map(|(c, n)| (format!("{c:?}"), Value::from(*n)))
+        .collect();
+    let format_versions: serde_json::Map<_, _> = level
+        .format_versions
+        .iter()
+        .map(|(v, n)| (v.to_string(), Value::from(*n)))
+        .collect();
+    let world_gen_steps: serde_json::Map<_, _> = level
+        .world_gen_steps
+        .iter()
+        .map(|(s, n)| (s.as_ref().to_string(), Value::from(*n)))
+        .collect();
+
+    json!({
+        "sections": level.sections,
+        "failed": level.failed,
+        "area": area_json(level.area),
+        "compression": compression,
+        "format_versions": format_versions,
+        "world_gen_steps": world_gen_steps,
+        "compressed_bytes": level.compressed_bytes,
+        "decompressed_bytes": level.decompressed_bytes,
+        "oldest": level.oldest,
+        "newest": level.newest,
+    })
+}
+
+fn print_level(name: &str, level: &LevelStats) {
+    print!("{name}: {} sections", level.sections);
+    if level.failed > 0 {
+        print!(" ({} failed to decompress)", level.failed);
+    }
+    println!();
+
+    if let Some(a) = level.area {
+        println!(
+            "    area x {}..={}, z {}..={} ({}x{} blocks)",
+            a.min_x,
+            a.max_x,
+            a.min_z,
+            a.max_z,
+            a.width(),
+            a.depth(),
+        );
+    }
+    println!(
+        "    {} KiB compressed, {} KiB decompressed",
+        level.compressed_bytes / 1024,
+        level.decompressed_bytes / 1024,
+    );
+    if let (Some(oldest), Some(newest)) = (level.oldest, level.newest) {
+        println!("    last modified {oldest}..={newest}");
+    }
+
+    let compression: Vec<_> = level
+        .compression
+        .iter()
+        .map(|(c, n)| format!("{c:?} {n}"))
+        .collect();
+    println!("    compression: {}", compression.join(", "));
+    let versions: Vec<_> = level
+        .format_versions
+        .iter()
+        .map(|(v, n)| {
+            if schema::is_supported_format_version(*v) {
+                format!("v{v} {n}")
+            } else {
+                format!("v{v} {n} (unsupported)")
+            }
+        })
+        .collect();
+    println!("    format versions: {}", versions.join(", "));
+    let steps: Vec<_> = level
+        .world_gen_steps
+        .iter()
+        .map(|(s, n)| format!("{} {n}", s.as_ref()))
+        .collect();
+    println!("    world gen steps (columns): {}", steps.join(", "));
+}
+
+pub fn run(conn: &Connection, options: Options) -> Result<()> {
+    let schema = Schema::detect(conn)?;
+    let stats = Stats::collect(conn, options.detail_level)?;
+    let total = stats.total();
+
+    if options.json {
+        let levels: Vec<_> = stats
+            .levels
+            .iter()
+            .map(|(detail_level, level)| {
+                let mut value = level_json(level);
+                value["detail_level"] = (*detail_level as u8).into();
+                value["name"] = format!("{detail_level:?}").into();
+                value
+            })
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "schema": {
+                    "version": schema.version(),
+                    "migrations": schema.migrations.len(),
+                },
+                "total": level_json(&total),
+                "detail_levels": levels,
+                "beacons": stats.beacons,
+            })
+        );
+        return Ok(());
+    }
+
+    match schema.version() {
+        Some(version) => println!(
+            "Schema version {version} ({} migrations)",
+            schema.migrations.len()
+        ),
+        None => println!("Schema version unknown, no migrations recorded"),
+    }
+    print_level("Total", &total);
+    println!("    {} beacon beams", stats.beacons);
+    for (detail_level, level) in &stats.levels {
+        print_level(&format!("{detail_level:?}"), level);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use anyhow::Result;
+use distant_horizons::backend::Connection;
+
+use distant_horizons::Section;
+
+use super::{Options, pos_json};
+
+pub fn run(conn: &Connection, options: Options) -> Result<()> {
+    Section::for_each(conn, options.detail_level, |section| {
+        if options.json {
+            let mut value = pos_json(section.pos);
+            value["min_y"] = section.min_y.into();
+            value["compression"] = format!("{:?}", section.compression()).into();
+            value["format_version"] = section.format_version().into();
+            value["last_modified"] = section.last_modified().into();
+            value["created"] = section.created().into();
+            println!("{value}");
+        } else {
+            println!(
+                "{}\tmin_y {}\t{:?}\tlast modified {}",
+                section.pos,
+                section.min_y,
+                section.compression(),
+                section.last_modified(),
+            );
+        }
+        Ok::<_, anyhow::Error>(())
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use distant_horizons::backend::Connection;
+use serde_json::json;
+
+use distant_horizons::{Section, repo::transaction};
+
+use super::Options;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Database to copy the sections from
+    pub source: PathBuf,
+}
+
+/// Copies every section of `source` that is missing in `dest` or was modified more recently.
+pub fn run(dest: &Connection, source: &Connection, options: Options) -> Result<()> {
+    let mut inserted = 0;
+    let mut replaced = 0;
+    let mut skipped = 0;
+
+    // One transaction, an interrupted merge leaves the destination untouched
+    transaction(dest, || {
+        Section::for_each(source, options.detail_level, |section| {
+            match Section::get(dest, section.pos)? {
+                Some(old) if old.last_modified() >= section.last_modified() => {
+                    skipped += 1;
+                    return Ok(());
+                }
+                Some(_) => replaced += 1,
+                None => inserted += 1,
+            }
+            section.upsert_into(dest)?;
+            Ok::<_, anyhow::Error>(())
+        })
+    })?;
+
+    if options.json {
+        println!(
+            "{}",
+            json!({ "inserted": inserted, "replaced": replaced, "skipped": skipped })
+        );
+    } else {
+        println!("{inserted} sections inserted, {replaced} replaced, {skipped} up to date");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+mod census;
+mod diff;
+mod dump;
+mod export;
+mod find;
+mod info;
+mod list;
+mod merge;
+mod propagate;
+mod prune;
+mod render;
+mod verify;
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use serde_json::{Value, json};
+
+use distant_horizons::{
+    DetailLevel,
+    block::Block,
+    database::Mode::{ReadOnly, ReadWrite},
+    minecraft::Dimension,
+    open_database,
+    section::mapping::Entry,
+    section::pos::Pos,
+};
+
+/// Inspect, convert and view Distant Horizons LoD databases.
+#[derive(Debug, Parser)]
+#[command(version)]
+pub struct Cli {
+    /// DH database, defaults to the one of `--dimension` in `MINECRAFT_WORLD`
+    #[arg(long, global = true, env = "DH_PATH")]
+    db: Option<PathBuf>,
+
+    /// Dimension to open when no database is given: overworld, nether or end
+    #[arg(long, global = true, default_value = "overworld")]
+    dimension: Dimension,
+
+    /// Only look at sections of this detail level, by name (`Chunk4`) or number (`6`)
+    #[arg(long, short = 'l', global = true)]
+    detail_level: Option<DetailLevel>,
+
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Statistics of the sections stored per detail level
+    #[command(alias = "stats")]
+    Info,
+    /// List the stored sections
+    List,
+    /// Print the decoded contents of one section, e.g. `Chunk4*-3,12`
+    Dump(dump::Args),
+    /// Decompress and decode every section, reporting the broken ones
+    Verify,
+    /// Count blocks and biomes by surface area and volume, defaults to the finest detail level
+    Census(census::Args),
+    /// Find all locations of a block or biome, using the finest detail level available
+    Find(find::Args),
+    /// Render the sections of one detail level into a PNG map
+    Render(render::Args),
+    /// Write the decoded sections as JSON lines
+    Export(export::Args),
+    /// Copy sections from another database into this one, the newer section wins
+    Merge(merge::Args),
+    /// Compare this database with a newer one or a backup
+    Diff(diff::Args),
+    /// Update the parents and children of sections DH flagged but didn't finish updating
+    Propagate(propagate::Args),
+    /// Remove the sections outside a border, of unneeded detail levels or older than a date
+    Prune(prune::Args),
+    /// Open the map viewer
+    #[cfg(feature = "gui")]
+    Gui,
+}
+
+/// Options shared by all subcommands.
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    pub detail_level: Option<DetailLevel>,
+    pub json: bool,
+}
+
+impl Cli {
+    #[inline]
+    pub fn run(self) -> Result<()> {
+        let db = self.db_path();
+        let options = Options {
+            detail_level: self.detail_level,
+            json: self.json,
+        };
+
+        match self.command {
+            Command::Info => info::run(&open_database(&db, ReadOnly)?, options),
+            Command::List => list::run(&open_database(&db, ReadOnly)?, options),
+            Command::Dump(args) => dump::run(&open_database(&db, ReadOnly)?, options, args),
+            Command::Verify => verify::run(&open_database(&db, ReadOnly)?, options),
+            Command::Census(args) => census::run(&open_database(&db, ReadOnly)?, options, args),
+            Command::Find(args) => find::run(&open_database(&db, ReadOnly)?, options, args),
+            Command::Render(args) => render::run(&open_database(&db, ReadOnly)?, options, args),
+            Command::Export(args) => export::run(&open_database(&db, ReadOnly)?, options, args),
+            Command::Merge(args) => {
+                let source = open_database(&args.source, ReadOnly)?;
+                merge::run(&open_database(&db, ReadWrite)?, &source, options)
+            }
+            Command::Diff(args) => {
+                let other = open_database(&args.other, ReadOnly)?;
+                diff::run(&open_database(&db, ReadOnly)?, &other, options, args)
+            }
+            Command::Propagate(args) => {
+                let mode = if args.dry_run { ReadOnly } else { ReadWrite };
+                propagate::run(&open_database(&db, mode)?, options, args)
+            }
+            Command::Prune(args) => {
+                let mode = if args.dry_run { ReadOnly } else { ReadWrite };
+                prune::run(&open_database(&db, mode)?, options, args)
+            }
+            #[cfg(feature = "gui")]
+            Command::Gui => Ok(distant_horizons::gui::main(db)?),
+        }
+    }
+
+    fn db_path(&self) -> PathBuf {
+        if let Some(db) = &self.db {
+            return db.clone();
+        }
+        match distant_horizons::minecraft::world_path() {
+            Some(world) => self.dimension.database_path(world),
+            None => distant_horizons::minecraft::DATABASE_FILE.into(),
+        }
+    }
+}
+
+fn pos_json(pos: Pos) -> Value {
+    json!({
+        "detail_level": pos.detail_level as u8,
+        "x": pos.x,
+        "z": pos.z,
+    })
+}
+
+fn entry_json(entry: &Entry) -> Value {
+    let state: serde_json::Map<String, Value> = entry
+        .states()
+        .map(|(k, v)| (k.to_string(), v.into()))
+        .collect();
+
+    json!({
+        "biome": entry.biome(),
+        "block": entry.full_identifier(),
+        "state": state,
+    })
+}
@@ -0,0 +1,69 @@
+use anyhow::Result;
+use distant_horizons::backend::Connection;
+use serde_json::json;
+
+use distant_horizons::{propagate::Propagation, repo::transaction};
+
+use super::{Options, pos_json};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Only report the sections that would be rewritten
+    #[arg(long, short = 'n')]
+    pub dry_run: bool,
+}
+
+/// Updates the parents and children of flagged sections and clears the `ApplyToChildren` flags.
+pub fn run(conn: &Connection, options: Options, args: Args) -> Result<()> {
+    let propagation = Propagation::plan(conn)?;
+
+    if !args.dry_run && !propagation.is_empty() {
+        // One transaction, an interrupted run leaves the flags for the next one
+        transaction(conn, || propagation.apply(conn))?;
+    }
+
+    if options.json {
+        let positions = |p: &[_]| p.iter().copied().map(pos_json).collect::<Vec<_>>();
+        let flagged: Vec<_> = propagation
+            .flagged
+            .iter()
+            .map(|f| {
+                let mut value = pos_json(f.pos);
+                value["apply_to_parent"] = f.apply_to_parent.into();
+                value["apply_to_children"] = f.apply_to_children.into();
+                value
+            })
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "dry_run": args.dry_run,
+                "flagged": flagged,
+                "children": positions(&propagation.children),
+                "parents": positions(&propagation.parents),
+            })
+        );
+        return Ok(());
+    }
+
+    let (children, parents) = (propagation.children.len(), propagation.parents.len());
+    if args.dry_run {
+        for pos in &propagation.children {
+            println!("fill {pos}");
+        }
+        for pos in &propagation.parents {
+            println!("rebuild {pos}");
+        }
+        println!(
+            "{} flagged sections, {children} children would be filled, {parents} parents rebuilt",
+            propagation.flagged.len()
+        );
+    } else {
+        println!(
+            "{} flagged sections, {children} children filled, {parents} parents rebuilt",
+            propagation.flagged.len()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use distant_horizons::backend::{self, Connection};
+use serde_json::json;
+
+use distant_horizons::{
+    DetailLevel,
+    prune::{self, Border, Prune},
+    repo::transaction,
+};
+
+use super::Options;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Remove the sections outside this rectangle, as `<min x>,<min z>,<max x>,<max z>` in blocks
+    #[arg(
+        long,
+        value_parser = parse_rectangle,
+        conflicts_with = "radius",
+        allow_hyphen_values = true
+    )]
+    border: Option<[i32; 4]>,
+
+    /// Remove the sections further than this many blocks from `--center`
+    #[arg(long)]
+    radius: Option<u32>,
+
+    /// Center of `--radius` as `<x>,<z>` in blocks
+    #[arg(
+        long,
+        value_parser = parse_center,
+        default_value = "0,0",
+        requires = "radius",
+        allow_hyphen_values = true
+    )]
+    center: (i32, i32),
+
+    /// Remove all sections of this detail level, can be repeated
+    #[arg(long)]
+    drop_level: Vec<DetailLevel>,
+
+    /// Remove the sections last modified before this date (`YYYY-MM-DD`) or Unix time in milliseconds
+    #[arg(long, value_parser = parse_time)]
+    modified_before: Option<i64>,
+
+    /// Don't rebuild the database file afterwards, it keeps its size.
+    /// Required with the duckdb backend, which can't rebuild it
+    #[arg(long)]
+    no_vacuum: bool,
+
+    /// Only report the sections that would be removed
+    #[arg(long, short = 'n')]
+    pub dry_run: bool,
+}
+
+fn parse_rectangle(s: &str) -> Result<[i32; 4]> {
+    let values: Vec<i32> = s
+        .split(',')
+        .map(|v| v.trim().parse())
+        .collect::<Result<_, _>>()?;
+    let [min_x, min_z, max_x, max_z] = values[..] else {
+        anyhow::bail!("expected <min x>,<min z>,<max x>,<max z>");
+    };
+    anyhow::ensure!(min_x <= max_x && min_z <= max_z, "min above max");
+    Ok([min_x, min_z, max_x, max_z])
+}
+
+fn parse_center(s: &str) -> Result<(i32, i32)> {
+    let (x, z) = s.split_once(',').context("expected <x>,<z>")?;
+    Ok((x.trim().parse()?, z.trim().parse()?))
+}
+
+fn parse_time(s: &str) -> Result<i64> {
+    Ok(prune::parse_time(s)?)
+}
+
+/// Deletes the sections outside a border, of unneeded detail levels or not modified recently.
+pub fn run(conn: &Connection, options: Options, args: Args) -> Result<()> {
+    let outside = match (args.border, args.radius) {
+        (Some([min_x, min_z, max_x, max_z]), _) => Some(Border::Rectangle {
+            min_x,
+            min_z,
+            max_x,
+            max_z,
+        }),
+        (None, Some(radius)) => Some(Border::Circle {
+            x: args.center.0,
+            z: args.center.1,
+            radius,
+        }),
+        (None, None) => None,
+    };
+    let prune = Prune {
+        detail_level: options.detail_level,
+        outside,
+        detail_levels: args.drop_level,
+        modified_before: args.modified_before,
+    };
+    anyhow::ensure!(
+        !prune.is_empty(),
+        "give a --border, --radius, --drop-level or --modified-before"
+    );
+    anyhow::ensure!(
+        args.dry_run || args.no_vacuum || backend::CAN_VACUUM,
+        "duckdb can't rebuild the sqlite file to shrink it, pass --no-vacuum or use a build with the sqlite feature"
+    );
+
+    let positions = prune.plan(conn)?;
+    let mut per_level = BTreeMap::<DetailLevel, usize>::new();
+    for pos in &positions {
+        *per_level.entry(pos.detail_level).or_default() += 1;
+    }
+
+    let mut removed = 0;
+    let mut vacuumed = false;
+    if !args.dry_run && !positions.is_empty() {
+        // One transaction, an interrupted run leaves the database untouched
+        removed = transaction(conn, || Prune::apply(conn, &positions))?;
+        // VACUUM can't run inside a transaction
+        if !args.no_vacuum {
+            backend::vacuum(conn)?;
+            vacuumed = true;
+        }
+    }
+
+    if options.json {
+        let per_level: serde_json::Map<_, _> = per_level
+            .iter()
+            .map(|(level, count)| (format!("{level:?}"), (*count).into()))
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "dry_run": args.dry_run,
+                "sections": positions.len(),
+                "removed": removed,
+                "detail_levels": per_level,
+                "vacuumed": vacuumed,
+            })
+        );
+        return Ok(());
+    }
+
+    for (level, count) in &per_level {
+        println!("{level:?}: {count}");
+    }
+    if args.dry_run {
+        println!("{} sections would be removed", positions.len());
+    } else {
+        println!("{removed} sections removed");
+        if removed > 0 && !vacuumed {
+            println!("the database file keeps its size until sqlite vacuums it");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use anyhow::{Context, Result};
+use distant_horizons::backend::Connection;
+use serde_json::json;
+
+use distant_horizons::{DetailLevel, Section, render::section_rgba};
+
+use super::Options;
+
+/// Longest side of the rendered image in pixels.
+const MAX_IMAGE_SIZE: usize = 16384;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// PNG file to write, one pixel per column
+    #[arg(short, long, default_value = "map.png")]
+    output: PathBuf,
+}
+
+pub fn run(conn: &Connection, options: Options, args: Args) -> Result<()> {
+    let detail_level = options.detail_level.unwrap_or(DetailLevel::Chunk16);
+
+    let mut sections = Vec::new();
+    Section::for_each(conn, Some(detail_level), |section| {
+        sections.push(section.into_owned());
+        Ok::<_, anyhow::Error>(())
+    })?;
+    anyhow::ensure!(!sections.is_empty(), "no sections at {detail_level:?}");
+
+    let min_x = sections.iter().map(|s| s.pos.x).min().unwrap_or_default();
+    let max_x = sections.iter().map(|s| s.pos.x).max().unwrap_or_default();
+    let min_z = sections.iter().map(|s| s.pos.z).min().unwrap_or_default();
+    let max_z = sections.iter().map(|s| s.pos.z).max().unwrap_or_default();
+
+    let width = (max_x - min_x + 1) as usize * Section::WIDTH;
+    let height = (max_z - min_z + 1) as usize * Section::WIDTH;
+    anyhow::ensure!(
+        width <= MAX_IMAGE_SIZE && height <= MAX_IMAGE_SIZE,
+        "{width}x{height} pixels is too large, pick a coarser detail level"
+    );
+
+    let mut image = vec![0; width * height * 4];
+    for mut section in sections {
+        if let Err(e) = section.decompress() {
+            eprintln!("Failed to decompress section {}: {e:#}", section.pos);
+            continue;
+        }
+        let Some(pixels) = section_rgba(&section) else {
+            continue;
+        };
+
+        let left = (section.pos.x - min_x) as usize * Section::WIDTH;
+        let top = (section.pos.z - min_z) as usize * Section::WIDTH;
+        for (row, line) in pixels.chunks_exact(Section::WIDTH * 4).enumerate() {
+            let offset = ((top + row) * width + left) * 4;
+            image[offset..offset + line.len()].copy_from_slice(line);
+        }
+    }
+
+    let file = File::create(&args.output)
+        .with_context(|| format!("creating {}", args.output.display()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&image)?;
+
+    let (x, z) = (
+        min_x * detail_level.block_width(),
+        min_z * detail_level.block_width(),
+    );
+    if options.json {
+        let output = args.output.to_string_lossy();
+        println!(
+            "{}",
+            json!({ "output": output, "width": width, "height": height, "x": x, "z": z })
+        );
+    } else {
+        println!(
+            "Rendered {width}x{height} pixels to {}, top left at x {x}, z {z}",
+            args.output.display(),
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use distant_horizons::backend::Connection;
+use serde_json::json;
+
+use distant_horizons::Section;
+
+use super::{Options, pos_json};
+
+/// Decompresses the section and checks that every data point refers to a mapping entry.
+fn check(section: &mut Section) -> Result<()> {
+    section.decompress()?;
+
+    let cols = section.column_data().context("missing column data")?;
+    let mapping = section.mapping().context("missing mapping")?;
+
+    for (i, col) in cols.as_ref().iter().enumerate() {
+        if let Some(point) = col.iter().find(|p| p.id() as usize >= mapping.len()) {
+            anyhow::bail!(
+                "column {i}: id {} outside of the mapping with {} entries",
+                point.id(),
+                mapping.len()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn run(conn: &Connection, options: Options) -> Result<()> {
+    let mut checked = 0;
+    let mut failed = 0;
+
+    Section::for_each(conn, options.detail_level, |mut section| {
+        checked += 1;
+        if let Err(e) = check(&mut section) {
+            failed += 1;
+            if options.json {
+                let mut value = pos_json(section.pos);
+                value["error"] = format!("{e:#}").into();
+                println!("{value}");
+            } else {
+                println!("{}: {e:#}", section.pos);
+            }
+        }
+        Ok::<_, anyhow::Error>(())
+    })?;
+
+    if options.json {
+        println!("{}", json!({ "checked": checked, "failed": failed }));
+    } else {
+        println!("{checked} sections checked, {failed} failed");
+    }
+
+    anyhow::ensure!(failed == 0, "{failed} sections failed verification");
+    Ok(())
+}
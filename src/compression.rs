@@ -1,11 +1,11 @@
 use std::{borrow::Cow, convert::Infallible};
 
-use duckdb::{
+use crate::backend::{
     Result,
     types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
 #[repr(u8)]
 pub enum Compression {
@@ -24,10 +24,8 @@ impl FromSql for Compression {
             Err(e) => Err(e),
             Ok(0) => Ok(Self::Uncompressed),
             Ok(1) => Ok(Self::Lz4),
-            Ok(2) => {
-                eprintln!("Database contains unsupported zstd-compressed data");
-                Ok(Self::Zstd)
-            }
+            // Read so the section can be listed, decompressing it fails
+            Ok(2) => Ok(Self::Zstd),
             Ok(3) => Ok(Self::Lzma2),
             _ => FromSqlResult::Err(FromSqlError::InvalidType),
         }
@@ -169,11 +167,11 @@ impl<'a, T, C> Compressed<'a, T, C> {
     }
 
     #[inline]
-    pub fn decompress(&mut self) -> Result<&T, anyhow::Error>
+    pub fn decompress(&mut self) -> crate::Result<&T>
     where
         Self: Default,
-        C: Decompressor<Error = anyhow::Error>,
-        T: TryFrom<Box<[u8]>, Error = anyhow::Error>,
+        C: Decompressor<Error = crate::Error>,
+        T: TryFrom<Box<[u8]>, Error = crate::Error>,
     {
         use core::mem::take;
         match self {
@@ -204,6 +202,36 @@ impl<'a, T, C> Compressed<'a, T, C> {
         }
     }
 
+    /// Encodes and compresses the value unless the compressed buffer is still around,
+    /// the value is kept as a cache.
+    #[inline]
+    pub fn compress(&mut self) -> crate::Result<&[u8]>
+    where
+        Self: Default,
+        C: Compressor<Error = crate::Error>,
+        for<'t> Box<[u8]>: TryFrom<&'t T, Error = crate::Error>,
+    {
+        use core::mem::take;
+        if let Self::Decompressed { val, compressor } = self {
+            // Encode before taking the value, a failure leaves it untouched
+            let buf = compressor.compress(Box::<[u8]>::try_from(&*val)?)?;
+
+            let Self::Decompressed { val, compressor } = take(self) else {
+                unreachable!();
+            };
+            *self = Self::Cached {
+                compressor,
+                val,
+                buf: Cow::Owned(buf.into_vec()),
+            };
+        }
+
+        match &*self {
+            Self::Compressed { buf, .. } | Self::Cached { buf, .. } => Ok(buf),
+            Self::Decompressed { .. } => unreachable!(),
+        }
+    }
+
     #[inline]
     pub fn drop_cache(&mut self)
     where
@@ -224,6 +252,32 @@ impl<'a, T, C> Compressed<'a, T, C> {
         }
     }
 
+    /// Drops the decompressed value if the compressed buffer is still around to restore it.
+    #[inline]
+    pub fn drop_value(&mut self)
+    where
+        Self: Default,
+    {
+        use core::mem::take;
+        if let Self::Cached { .. } = self {
+            let Self::Cached {
+                buf, compressor, ..
+            } = take(self)
+            else {
+                unreachable!();
+            };
+            *self = Self::Compressed { compressor, buf }
+        }
+    }
+
+    #[inline]
+    pub fn compressed_len(&self) -> usize {
+        match self {
+            Self::Compressed { buf, .. } | Self::Cached { buf, .. } => buf.len(),
+            Self::Decompressed { .. } => 0,
+        }
+    }
+
     /// Returns a mutable reference to the decompressed value, if it is already decompressed.
     /// Invalidates the cache.
     #[inline]
@@ -256,7 +310,7 @@ impl<T, C> ToSql for Compressed<'_, T, C> {
     fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
         match self {
             Self::Compressed { buf, .. } | Self::Cached { buf, .. } => Ok(buf.as_ref().into()),
-            Self::Decompressed { .. } => Err(duckdb::Error::ToSqlConversionFailure(
+            Self::Decompressed { .. } => Err(crate::backend::Error::ToSqlConversionFailure(
                 "Value needs to be compressed first".into(),
             )),
         }
@@ -279,21 +333,22 @@ impl<'a, T, C: Default> TryFrom<ValueRef<'a>> for Compressed<'a, T, C> {
 }
 
 #[inline]
-pub fn decompress_lzma(raw: &[u8]) -> Result<Box<[u8]>, anyhow::Error> {
+pub fn decompress_lzma(raw: &[u8]) -> crate::Result<Box<[u8]>> {
     use std::io::Read;
     use xz2::bufread::XzDecoder;
+    let error = |message: String| crate::Error::Decompress {
+        compression: Compression::Lzma2,
+        message,
+    };
+
     let mut decoder = XzDecoder::new(raw);
     let mut output = Vec::new();
     match decoder.read_to_end(&mut output) {
-        Ok(b) if b == raw.len() => {}
-        Ok(..) => {
-            anyhow::bail!("failed to decompress LZMA: incomplete output");
-        }
+        Ok(_) if decoder.total_in() == raw.len() as u64 => {}
+        Ok(_) => return Err(error("trailing data after the stream".into())),
         // Ignore EOF
         Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {}
-        Err(e) => {
-            return Err(anyhow::Error::new(e).context("failed to decompress LZMA"));
-        }
+        Err(e) => return Err(error(e.to_string())),
     }
 
     debug_assert_eq!(decoder.total_in(), raw.len() as u64);
@@ -302,18 +357,21 @@ pub fn decompress_lzma(raw: &[u8]) -> Result<Box<[u8]>, anyhow::Error> {
     Ok(output.into_boxed_slice())
 }
 
+/// Compresses into the xz container [`decompress_lzma`] reads.
 #[inline]
-pub fn compress_lzma(value: Box<[u8]>) -> Box<[u8]> {
-    const LZMA_OPTIONS: core::cell::LazyCell<xz2::stream::LzmaOptions> =
-        core::cell::LazyCell::new(|| xz2::stream::LzmaOptions::new_preset(9).unwrap());
-
-    let mut encoder = xz2::stream::Stream::new_lzma_encoder(&LZMA_OPTIONS).unwrap();
-    let mut output = Vec::new();
-    encoder
-        .process_vec(&value, &mut output, xz2::stream::Action::Finish)
-        .unwrap();
+pub fn compress_lzma(value: &[u8]) -> crate::Result<Box<[u8]>> {
+    use std::io::Write;
+    use xz2::write::XzEncoder;
+    let error = |e: std::io::Error| crate::Error::Compress {
+        compression: Compression::Lzma2,
+        message: e.to_string(),
+    };
+
+    let mut encoder = XzEncoder::new(Vec::new(), 9);
+    encoder.write_all(value).map_err(error)?;
+    let output = encoder.finish().map_err(error)?;
 
-    output.into()
+    Ok(output.into_boxed_slice())
 }
 
 pub trait Decompressor {
@@ -323,14 +381,33 @@ pub trait Decompressor {
 }
 
 impl Decompressor for Compression {
-    type Error = anyhow::Error;
+    type Error = crate::Error;
 
     #[inline]
     fn decompress(&mut self, raw: impl AsRef<[u8]>) -> Result<Box<[u8]>, Self::Error> {
         match *self {
             Self::Uncompressed => Ok(raw.as_ref().into()),
             Self::Lzma2 => decompress_lzma(raw.as_ref()),
-            _ => todo!(),
+            c => Err(crate::Error::UnsupportedCompression(c)),
+        }
+    }
+}
+
+pub trait Compressor {
+    type Error;
+
+    fn compress(&mut self, raw: impl AsRef<[u8]>) -> Result<Box<[u8]>, Self::Error>;
+}
+
+impl Compressor for Compression {
+    type Error = crate::Error;
+
+    #[inline]
+    fn compress(&mut self, raw: impl AsRef<[u8]>) -> Result<Box<[u8]>, Self::Error> {
+        match *self {
+            Self::Uncompressed => Ok(raw.as_ref().into()),
+            Self::Lzma2 => compress_lzma(raw.as_ref()),
+            c => Err(crate::Error::UnsupportedCompression(c)),
         }
     }
 }
@@ -379,3 +456,28 @@ impl Decompressor for Compression {
 //         }
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lzma_round_trip() {
+        let raw: Vec<u8> = (0..10_000u32)
+            .flat_map(|i| (i % 251).to_be_bytes())
+            .collect();
+        let compressed = compress_lzma(&raw).unwrap();
+        assert!(compressed.len() < raw.len());
+        assert_eq!(&*decompress_lzma(&compressed).unwrap(), &raw[..]);
+
+        let empty = compress_lzma(&[]).unwrap();
+        assert!(decompress_lzma(&empty).unwrap().is_empty());
+    }
+
+    #[test]
+    fn lzma_trailing_data() {
+        let mut compressed = compress_lzma(b"distant horizons").unwrap().into_vec();
+        compressed.extend_from_slice(b"garbage");
+        assert!(decompress_lzma(&compressed).is_err());
+    }
+}
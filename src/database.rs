@@ -0,0 +1,176 @@
+use std::path::Path;
+
+use crate::{Error, backend::Connection, schema::Schema};
+
+/// How the DH database is attached.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    #[default]
+    ReadOnly,
+    ReadWrite,
+}
+
+impl Mode {
+    #[cfg(all(feature = "duckdb", not(feature = "sqlite")))]
+    #[inline]
+    #[must_use]
+    const fn as_sql(self) -> &'static str {
+        match self {
+            Self::ReadOnly => "READONLY",
+            Self::ReadWrite => "READWRITE",
+        }
+    }
+}
+
+#[cfg(any(test, all(feature = "duckdb", not(feature = "sqlite"))))]
+/// Quotes a string literal for SQL, doubling embedded quotes.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Opens a DH sqlite database, see [`crate::backend`] for how.
+///
+/// Fails if the [`Schema`] lacks what reading, or with [`Mode::ReadWrite`] writing, sections needs.
+#[inline]
+pub fn open_database(path: impl AsRef<Path>, mode: Mode) -> crate::Result<Connection> {
+    let path = path.as_ref();
+    let conn = open(path, mode).map_err(|source| match source {
+        Error::Database(source) => Error::Open {
+            path: path.to_path_buf(),
+            source,
+        },
+        e => e,
+    })?;
+
+    let schema = Schema::detect(&conn)?;
+    match mode {
+        Mode::ReadOnly => schema.check_readable()?,
+        Mode::ReadWrite => schema.check_writable()?,
+    }
+
+    Ok(conn)
+}
+
+/// Attaches the database through duckdb's sqlite extension as the default schema `dh`.
+///
+/// `ATTACH` doesn't take bound parameters, the path is quoted as a string literal instead.
+#[cfg(all(feature = "duckdb", not(feature = "sqlite")))]
+fn open(path: &Path, mode: Mode) -> Result<Connection, Error> {
+    let literal = path
+        .to_str()
+        .filter(|p| !p.contains('\0'))
+        .map(quote)
+        .ok_or_else(|| Error::InvalidPath(path.to_path_buf()))?;
+
+    let conn = Connection::open_in_memory()?;
+    conn.execute("INSTALL SQLITE", [])?;
+    conn.execute(
+        &format!("ATTACH {literal} AS dh (TYPE SQLITE, {})", mode.as_sql()),
+        [],
+    )?;
+    conn.execute("SET sqlite_all_varchar=true", [])?;
+    conn.execute("USE dh", [])?;
+
+    Ok(conn)
+}
+
+/// Opens the database file directly, it must already exist.
+#[cfg(feature = "sqlite")]
+fn open(path: &Path, mode: Mode) -> Result<Connection, Error> {
+    use rusqlite::OpenFlags;
+
+    let flags = match mode {
+        Mode::ReadOnly => OpenFlags::SQLITE_OPEN_READ_ONLY,
+        Mode::ReadWrite => OpenFlags::SQLITE_OPEN_READ_WRITE,
+    };
+
+    Ok(Connection::open_with_flags(
+        path,
+        flags | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?)
+}
+
+/// The tables DH creates.
+#[cfg(all(test, feature = "sqlite"))]
+const TABLES: &str = "
+    CREATE TABLE Schema(ScriptName TEXT PRIMARY KEY, AppliedDateTime DATETIME);
+    CREATE TABLE FullData(
+        DetailLevel TINYINT NOT NULL,
+        PosX INT NOT NULL,
+        PosZ INT NOT NULL,
+        MinY INT NOT NULL,
+        DataChecksum INT NOT NULL,
+        Data BLOB NULL,
+        ColumnGenerationStep BLOB NULL,
+        ColumnWorldCompressionMode BLOB NULL,
+        Mapping BLOB NULL,
+        DataFormatVersion TINYINT NULL,
+        CompressionMode TINYINT NULL,
+        ApplyToParent BIT NULL,
+        ApplyToChildren BIT NULL,
+        LastModifiedUnixDateTime BIGINT NOT NULL,
+        CreatedUnixDateTime BIGINT NOT NULL,
+        PRIMARY KEY (DetailLevel, PosX, PosZ)
+    );
+    CREATE TABLE BeaconBeam(
+        BlockPosX INT NOT NULL,
+        BlockPosY INT NOT NULL,
+        BlockPosZ INT NOT NULL,
+        ColorR INT,
+        ColorG INT,
+        ColorB INT,
+        LastModifiedUnixDateTime BIGINT NOT NULL,
+        CreatedUnixDateTime BIGINT NOT NULL,
+        PRIMARY KEY (BlockPosX, BlockPosY, BlockPosZ)
+    );
+";
+
+/// An in-memory database with the tables DH creates.
+#[cfg(all(test, feature = "sqlite"))]
+pub(crate) fn open_in_memory() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(TABLES).unwrap();
+    conn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("a'b.sqlite"), "'a''b.sqlite'");
+        assert_eq!(quote("''"), "''''''");
+        assert_eq!(
+            quote("/saves/DistantHorizons.sqlite"),
+            "'/saves/DistantHorizons.sqlite'"
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn open_quoted_path() {
+        let dir = std::env::temp_dir().join(format!("dh-open-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a'b.sqlite");
+        let _ = std::fs::remove_file(&path);
+
+        // The file isn't created
+        assert!(matches!(
+            open_database(&path, Mode::ReadWrite),
+            Err(Error::Open { path: p, .. }) if p == path
+        ));
+        assert!(!path.exists());
+
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(TABLES)
+            .unwrap();
+        for mode in [Mode::ReadOnly, Mode::ReadWrite] {
+            let conn = open_database(&path, mode).unwrap();
+            assert!(crate::Section::get_all(&conn).unwrap().is_empty());
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -54,21 +54,62 @@ impl DetailLevel {
     pub const fn block_width(self) -> i32 {
         1 << (self as u8)
     }
+
+    /// The next finer level, `None` for [`Self::Block`].
+    #[inline]
+    #[must_use]
+    pub const fn finer(self) -> Option<Self> {
+        match self {
+            Self::Block => None,
+            // Safety: every level but `Block` has a predecessor
+            d => Some(unsafe { Self::new_unchecked(d as u8 - 1) }),
+        }
+    }
+
+    /// The next coarser level, `None` for [`Self::MAX`].
+    #[inline]
+    #[must_use]
+    pub const fn coarser(self) -> Option<Self> {
+        match Self::try_new(self as u8 + 1) {
+            Ok(level) => Some(level),
+            Err(()) => None,
+        }
+    }
 }
 
-impl duckdb::ToSql for DetailLevel {
+impl crate::backend::ToSql for DetailLevel {
     #[inline]
-    fn to_sql(&self) -> duckdb::Result<duckdb::types::ToSqlOutput<'_>> {
-        use duckdb::types::{ToSqlOutput, Value};
+    fn to_sql(&self) -> crate::backend::Result<crate::backend::types::ToSqlOutput<'_>> {
+        use crate::backend::types::{ToSqlOutput, Value};
         Ok(ToSqlOutput::Owned(Value::from(*self as u8)))
     }
 }
 
-impl duckdb::types::FromSql for DetailLevel {
+impl crate::backend::types::FromSql for DetailLevel {
     #[inline]
-    fn column_result(value: duckdb::types::ValueRef<'_>) -> duckdb::types::FromSqlResult<Self> {
+    fn column_result(
+        value: crate::backend::types::ValueRef<'_>,
+    ) -> crate::backend::types::FromSqlResult<Self> {
         let value = u8::column_result(value)?;
-        Self::try_new(value).map_err(|_| duckdb::types::FromSqlError::InvalidType)
+        Self::try_new(value).map_err(|_| crate::backend::types::FromSqlError::InvalidType)
+    }
+}
+
+impl core::str::FromStr for DetailLevel {
+    type Err = crate::Error;
+
+    /// Parses either the name (`Chunk16`, case-insensitive) or the numeric level (`8`).
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(level) = s.parse::<u8>() {
+            return Self::try_new(level)
+                .map_err(|()| crate::Error::parse(format!("detail level out of range: {level}")));
+        }
+
+        (Self::MIN as u8..=Self::MAX as u8)
+            .filter_map(|level| Self::try_new(level).ok())
+            .find(|level| format!("{level:?}").eq_ignore_ascii_case(s))
+            .ok_or_else(|| crate::Error::parse(format!("unknown detail level: {s:?}")))
     }
 }
 
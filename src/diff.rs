@@ -0,0 +1,323 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    DetailLevel, Error, Result, Section,
+    block::Block,
+    section::{
+        data::DataPoint,
+        mapping::{Entry, Mapping},
+        pos::Pos,
+    },
+};
+
+/// Topmost non-air data point of a column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Surface {
+    pub block: String,
+    /// World height of the top of the data point
+    pub y: i32,
+}
+
+/// A column that differs between two versions of a section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnChange {
+    /// Column inside the section, 0..64
+    pub dx: usize,
+    pub dz: usize,
+    pub before: Option<Surface>,
+    pub after: Option<Surface>,
+}
+
+impl ColumnChange {
+    #[inline]
+    #[must_use]
+    pub fn block_changed(&self) -> bool {
+        self.before.as_ref().map(|s| &s.block) != self.after.as_ref().map(|s| &s.block)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn height_changed(&self) -> bool {
+        self.before.as_ref().map(|s| s.y) != self.after.as_ref().map(|s| s.y)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SectionDiff {
+    Added(Pos),
+    Removed(Pos),
+    /// Columns that differ, empty if only the metadata differs
+    Changed(Pos, Vec<ColumnChange>),
+    /// Changed, but one of the versions couldn't be decoded to compare the columns
+    Unreadable(Pos),
+}
+
+impl SectionDiff {
+    #[inline]
+    #[must_use]
+    pub const fn pos(&self) -> Pos {
+        match self {
+            Self::Added(pos)
+            | Self::Removed(pos)
+            | Self::Changed(pos, _)
+            | Self::Unreadable(pos) => *pos,
+        }
+    }
+}
+
+/// Checksum and modification time of every section, without the data.
+fn index(
+    conn: &crate::backend::Connection,
+    detail_level: Option<DetailLevel>,
+) -> Result<BTreeMap<Pos, (i32, i64)>> {
+    let mut index = BTreeMap::new();
+    Section::for_each(conn, detail_level, |section| {
+        index.insert(section.pos, (section.checksum(), section.last_modified()));
+        Ok::<_, Error>(())
+    })?;
+    Ok(index)
+}
+
+/// Compares the sections of two databases, ordered by position.
+///
+/// Sections with equal checksum and modification time are assumed to be equal
+/// and not decompressed.
+pub fn diff(
+    before: &crate::backend::Connection,
+    after: &crate::backend::Connection,
+    detail_level: Option<DetailLevel>,
+) -> Result<Vec<SectionDiff>> {
+    let old = index(before, detail_level)?;
+    let new = index(after, detail_level)?;
+
+    let mut diffs = Vec::new();
+    for (pos, old_meta) in &old {
+        match new.get(pos) {
+            None => diffs.push(SectionDiff::Removed(*pos)),
+            Some(new_meta) if new_meta == old_meta => {}
+            Some(_) => {
+                let disappeared = || Error::format(format!("section {pos} disappeared"));
+                let mut a = Section::get(before, *pos)?.ok_or_else(disappeared)?;
+                let mut b = Section::get(after, *pos)?.ok_or_else(disappeared)?;
+                let columns = a
+                    .decompress()
+                    .and_then(|()| b.decompress())
+                    .and_then(|()| diff_columns(&a, &b));
+                diffs.push(match columns {
+                    Ok(columns) => SectionDiff::Changed(*pos, columns),
+                    Err(e) => {
+                        tracing::warn!("Can't compare section {pos}: {e:#}");
+                        SectionDiff::Unreadable(*pos)
+                    }
+                });
+            }
+        }
+    }
+    diffs.extend(
+        new.keys()
+            .filter(|pos| !old.contains_key(pos))
+            .map(|pos| SectionDiff::Added(*pos)),
+    );
+    diffs.sort_unstable_by_key(SectionDiff::pos);
+
+    Ok(diffs)
+}
+
+/// Entry of `point`, an error if the id is outside the mapping.
+fn entry<'m>(pos: Pos, mapping: &'m Mapping, point: &DataPoint) -> Result<&'m Entry> {
+    mapping.get(point.id() as usize).ok_or_else(|| {
+        Error::format(format!(
+            "data point id {} outside the mapping of section {pos}",
+            point.id()
+        ))
+    })
+}
+
+fn same_point(
+    pos: Pos,
+    a: &DataPoint,
+    a_map: &Mapping,
+    b: &DataPoint,
+    b_map: &Mapping,
+) -> Result<bool> {
+    Ok(a.height() == b.height()
+        && a.min_y() == b.min_y()
+        && a.sky_light() == b.sky_light()
+        && a.block_light() == b.block_light()
+        && entry(pos, a_map, a)? == entry(pos, b_map, b)?)
+}
+
+fn surface(section: &Section, col: &[DataPoint], mapping: &Mapping) -> Result<Option<Surface>> {
+    for p in col {
+        let entry = entry(section.pos, mapping, p)?;
+        if !entry.is_air() {
+            return Ok(Some(Surface {
+                block: entry.full_identifier().to_string(),
+                y: section.min_y + i32::from(p.min_y()) + i32::from(p.height()),
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Columns that differ between two decompressed versions of the same section.
+///
+/// Fails if a data point refers to an entry outside its section's mapping.
+#[inline]
+pub fn diff_columns(before: &Section, after: &Section) -> Result<Vec<ColumnChange>> {
+    let (Some(a_cols), Some(a_map), Some(b_cols), Some(b_map)) = (
+        before.column_data(),
+        before.mapping(),
+        after.column_data(),
+        after.mapping(),
+    ) else {
+        return Ok(Vec::new());
+    };
+
+    let mut changes = Vec::new();
+    for (dx, dz, a, b) in a_cols.zip(b_cols) {
+        let mut equal = a.len() == b.len();
+        for (a, b) in a.iter().zip(b.iter()) {
+            if !equal {
+                break;
+            }
+            equal = same_point(before.pos, a, a_map, b, b_map)?;
+        }
+        if !equal {
+            changes.push(ColumnChange {
+                dx,
+                dz,
+                before: surface(before, a, a_map)?,
+                after: surface(after, b, b_map)?,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Compression,
+        section::builder::{Run, SectionBuilder},
+    };
+
+    fn pos(x: i32) -> Pos {
+        Pos::new(DetailLevel::Chunk4, x, 0)
+    }
+
+    /// Stone with `top` on it in column 3,5 and a lone stone column at 7,7 unless `top` is sand.
+    fn section(pos: Pos, modified: i64, top: Option<(&str, i32)>) -> Section<'static> {
+        let run = |block: &str, y| Run::new(Entry::new("minecraft:plains", block), y);
+        let mut builder = SectionBuilder::new(pos)
+            .with_compression(Compression::Uncompressed)
+            .with_timestamps(0, modified);
+        builder.push(3, 5, run("minecraft:stone", -64..0)).unwrap();
+        if let Some((block, height)) = top {
+            builder.push(3, 5, run(block, 0..height)).unwrap();
+        }
+        if top.is_none_or(|(block, _)| block != "minecraft:sand") {
+            builder.push(7, 7, run("minecraft:stone", -64..0)).unwrap();
+        }
+        builder.build().unwrap()
+    }
+
+    fn surface(block: &str, y: i32) -> Option<Surface> {
+        Some(Surface {
+            block: block.to_string(),
+            y,
+        })
+    }
+
+    #[test]
+    fn columns() {
+        let grass = section(pos(0), 0, Some(("minecraft:grass_block", 1)));
+        assert!(diff_columns(&grass, &grass).unwrap().is_empty());
+        assert!(
+            diff_columns(
+                &grass,
+                &section(pos(0), 1, Some(("minecraft:grass_block", 1)))
+            )
+            .unwrap()
+            .is_empty()
+        );
+
+        let changes =
+            diff_columns(&grass, &section(pos(0), 0, Some(("minecraft:sand", 3)))).unwrap();
+        assert_eq!(
+            changes,
+            [
+                ColumnChange {
+                    dx: 3,
+                    dz: 5,
+                    before: surface("minecraft:grass_block", 1),
+                    after: surface("minecraft:sand", 3),
+                },
+                ColumnChange {
+                    dx: 7,
+                    dz: 7,
+                    before: surface("minecraft:stone", 0),
+                    after: None,
+                },
+            ]
+        );
+        assert!(changes[0].block_changed() && changes[0].height_changed());
+
+        let changes = |top| diff_columns(&grass, &section(pos(0), 0, Some(top))).unwrap();
+        let dirt = changes(("minecraft:dirt", 1));
+        assert!(dirt[0].block_changed() && !dirt[0].height_changed());
+        let taller = changes(("minecraft:grass_block", 2));
+        assert!(!taller[0].block_changed() && taller[0].height_changed());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn databases() {
+        let before = crate::database::open_in_memory();
+        let after = crate::database::open_in_memory();
+        let insert = |conn, pos, modified, top| {
+            let mut section = section(pos, modified, top);
+            section.compress().unwrap();
+            section.upsert_into(conn).unwrap();
+        };
+        let grass = Some(("minecraft:grass_block", 1));
+
+        // Unchanged
+        insert(&before, pos(0), 0, grass);
+        insert(&after, pos(0), 0, grass);
+        // Changed data
+        insert(&before, pos(1), 0, grass);
+        insert(&after, pos(1), 1, Some(("minecraft:sand", 3)));
+        // Changed modification time only
+        insert(&before, pos(2), 0, grass);
+        insert(&after, pos(2), 1, grass);
+        // Removed
+        insert(&before, pos(3), 0, grass);
+        // Corrupted
+        insert(&before, pos(4), 0, grass);
+        insert(&after, pos(4), 1, grass);
+        after
+            .execute("UPDATE FullData SET Data = x'00' WHERE PosX = 4", [])
+            .unwrap();
+        // Added
+        insert(&after, pos(5), 0, grass);
+        // Other detail levels are left out
+        insert(&after, Pos::new(DetailLevel::Chunk8, 0, 0), 0, grass);
+
+        let diffs = diff(&before, &after, Some(DetailLevel::Chunk4)).unwrap();
+        assert!(
+            matches!(&diffs[0], SectionDiff::Changed(p, columns) if *p == pos(1) && columns.len() == 2)
+        );
+        assert_eq!(
+            diffs[1..],
+            [
+                SectionDiff::Changed(pos(2), Vec::new()),
+                SectionDiff::Removed(pos(3)),
+                SectionDiff::Unreadable(pos(4)),
+                SectionDiff::Added(pos(5)),
+            ]
+        );
+        assert_eq!(diff(&before, &after, None).unwrap().len(), 6);
+    }
+}
@@ -0,0 +1,155 @@
+use std::path::PathBuf;
+
+use crate::{Compression, backend, section::pos::Pos};
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading or writing a file failed
+    Io(std::io::Error),
+    /// The path is not valid UTF-8 or contains a NUL byte, duckdb can't attach it
+    InvalidPath(PathBuf),
+    /// The database could not be opened
+    Open {
+        path: PathBuf,
+        source: backend::Error,
+    },
+    /// A database query failed
+    Database(backend::Error),
+    /// The compressed data is corrupt or truncated
+    Decompress {
+        compression: Compression,
+        message: String,
+    },
+    /// The data could not be compressed
+    Compress {
+        compression: Compression,
+        message: String,
+    },
+    /// The data uses a compression mode that isn't implemented
+    UnsupportedCompression(Compression),
+    /// The data doesn't have the layout DH writes
+    Format(String),
+    /// The data was written by a DH version this crate can't read
+    UnsupportedVersion { what: &'static str, version: i64 },
+    /// The database lacks tables or columns this crate needs
+    UnsupportedSchema {
+        /// Highest applied migration, if the database records them
+        version: Option<u32>,
+        missing: String,
+    },
+    /// A position, detail level or other value given as text is invalid
+    Parse(String),
+    /// Decompressing or decoding one part of a section failed
+    Section {
+        pos: Pos,
+        field: &'static str,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    #[inline]
+    pub(crate) fn format(message: impl Into<String>) -> Self {
+        Self::Format(message.into())
+    }
+
+    #[inline]
+    pub(crate) fn parse(message: impl Into<String>) -> Self {
+        Self::Parse(message.into())
+    }
+}
+
+/// Only the message of this error, `{:#}` appends the ones of its sources like `anyhow` does.
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Io(_) => f.write_str("I/O error"),
+            Self::InvalidPath(path) => write!(f, "unsupported database path: {}", path.display()),
+            Self::Open { path, .. } => write!(f, "failed to open {}", path.display()),
+            Self::Database(_) => f.write_str("database query failed"),
+            Self::Decompress {
+                compression,
+                message,
+            } => write!(f, "failed to decompress {compression:?} data: {message}"),
+            Self::Compress {
+                compression,
+                message,
+            } => write!(f, "failed to compress {compression:?} data: {message}"),
+            Self::UnsupportedCompression(c) => write!(f, "unsupported compression: {c:?}"),
+            Self::Format(message) => write!(f, "invalid data: {message}"),
+            Self::UnsupportedVersion { what, version } => {
+                write!(f, "unsupported {what} version {version}")
+            }
+            Self::UnsupportedSchema { version, missing } => {
+                f.write_str("unsupported database schema")?;
+                if let Some(version) = version {
+                    write!(f, " version {version}")?;
+                }
+                write!(f, ", missing {missing}")
+            }
+            Self::Parse(message) => f.write_str(message),
+            Self::Section { pos, field, .. } => write!(f, "section {pos}, {field}"),
+        }?;
+
+        if f.alternate() {
+            let mut source = std::error::Error::source(self);
+            while let Some(e) = source {
+                write!(f, ": {e}")?;
+                source = e.source();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The messages leave out the underlying error, error chains print it once.
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Open { source, .. } | Self::Database(source) => Some(source),
+            Self::Section { source, .. } => Some(&**source),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    #[inline]
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<backend::Error> for Error {
+    #[inline]
+    fn from(e: backend::Error) -> Self {
+        Self::Database(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_chain() {
+        let e = Error::Section {
+            pos: "Chunk4*1,-2".parse().unwrap(),
+            field: "mapping",
+            source: Box::new(Error::Io(std::io::Error::other("truncated"))),
+        };
+        assert_eq!(e.to_string(), "section Chunk4*1,-2, mapping");
+        assert_eq!(
+            format!("{e:#}"),
+            "section Chunk4*1,-2, mapping: I/O error: truncated"
+        );
+
+        let source = std::error::Error::source(&e).unwrap();
+        assert_eq!(source.to_string(), "I/O error");
+        assert_eq!(source.source().unwrap().to_string(), "truncated");
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    DetailLevel, Error, Result, Section,
+    block::Block,
+    section::{columns, mapping::Entry, pos::Pos},
+};
+
+/// What to search for: a block with optional block states, a biome or both.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pattern {
+    block: Option<String>,
+    states: Vec<(String, String)>,
+    biome: Option<String>,
+}
+
+/// Adds the `minecraft` namespace to identifiers without one.
+fn qualify(identifier: &str) -> String {
+    if identifier.contains(':') {
+        identifier.to_string()
+    } else {
+        format!("minecraft:{identifier}")
+    }
+}
+
+impl Pattern {
+    /// Parses `id` or `id[key=value,...]`, the namespace defaults to `minecraft`.
+    #[inline]
+    pub fn block(pattern: &str) -> Result<Self> {
+        let (id, states) = match pattern.split_once('[') {
+            Some((id, states)) => (
+                id,
+                states
+                    .strip_suffix(']')
+                    .ok_or_else(|| Error::parse("missing closing bracket"))?,
+            ),
+            None => (pattern, ""),
+        };
+        if id.is_empty() {
+            return Err(Error::parse("missing block identifier"));
+        }
+
+        let states = states
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|kv| {
+                let (k, v) = kv
+                    .split_once('=')
+                    .ok_or_else(|| Error::parse(format!("expected key=value, got {kv:?}")))?;
+                Ok((k.trim().to_string(), v.trim().to_string()))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            block: Some(qualify(id.trim())),
+            states,
+            biome: None,
+        })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn biome(biome: &str) -> Self {
+        Self::default().with_biome(biome)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_biome(mut self, biome: &str) -> Self {
+        self.biome = Some(qualify(biome));
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.block.is_none() && self.biome.is_none()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn matches(&self, entry: &Entry) -> bool {
+        // Biome searches look at the terrain, not the air above it
+        if self.block.is_none() && entry.is_air() {
+            return false;
+        }
+        if let Some(block) = &self.block
+            && !entry.full_identifier().eq_ignore_ascii_case(block)
+        {
+            return false;
+        }
+        if let Some(biome) = &self.biome
+            && !entry.biome().eq_ignore_ascii_case(biome)
+        {
+            return false;
+        }
+        self.states
+            .iter()
+            .all(|(k, v)| entry.get_state(k) == Some(v.as_str()))
+    }
+}
+
+/// A column span matching a [`Pattern`], in world coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Hit {
+    pub x: i32,
+    pub z: i32,
+    /// Width of the column in blocks, depends on the detail level
+    pub width: i32,
+    pub min_y: i32,
+    /// Exclusive
+    pub max_y: i32,
+}
+
+/// Nearby hits merged into one place, corners inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub min: (i32, i32, i32),
+    pub max: (i32, i32, i32),
+    pub hits: usize,
+}
+
+impl Location {
+    #[inline]
+    #[must_use]
+    pub const fn center(&self) -> (i32, i32, i32) {
+        (
+            self.min.0 + (self.max.0 - self.min.0) / 2,
+            self.min.1 + (self.max.1 - self.min.1) / 2,
+            self.min.2 + (self.max.2 - self.min.2) / 2,
+        )
+    }
+}
+
+impl From<Hit> for Location {
+    #[inline]
+    fn from(hit: Hit) -> Self {
+        Self {
+            min: (hit.x, hit.min_y, hit.z),
+            max: (hit.x + hit.width - 1, hit.max_y - 1, hit.z + hit.width - 1),
+            hits: 1,
+        }
+    }
+}
+
+/// Matching data points of a decompressed section.
+#[inline]
+pub fn section_hits(section: &Section, pattern: &Pattern) -> Vec<Hit> {
+    let (Some(cols), Some(mapping)) = (section.column_data(), section.mapping()) else {
+        return Vec::new();
+    };
+    let matching: Vec<bool> = mapping.iter().map(|e| pattern.matches(e)).collect();
+    if !matching.contains(&true) {
+        return Vec::new();
+    }
+
+    let width = section.width();
+    let mut hits = Vec::new();
+    for (dx, dz, col) in cols.iter() {
+        // Sections are never finer than `Pos::SECTION_MINIMUM_DETAIL_LEVEL`
+        let Some((x, z)) = columns::to_world(section.pos, dx, dz) else {
+            break;
+        };
+        for point in col.iter() {
+            if !matching.get(point.id() as usize).copied().unwrap_or(false) {
+                continue;
+            }
+            let min_y = section.min_y + i32::from(point.min_y());
+            hits.push(Hit {
+                x,
+                z,
+                width,
+                min_y,
+                max_y: min_y + i32::from(point.height()),
+            });
+        }
+    }
+    hits
+}
+
+/// Searches the sections for `pattern`, using the finest detail level available at every place.
+///
+/// Sections are scanned from fine to coarse, hits inside an area already scanned at a finer
+/// detail level are dropped. With `detail_level` only that level is scanned.
+pub fn find(
+    conn: &crate::backend::Connection,
+    pattern: &Pattern,
+    detail_level: Option<DetailLevel>,
+) -> Result<Vec<Hit>> {
+    let levels: Vec<DetailLevel> = match detail_level {
+        Some(detail_level) => vec![detail_level],
+        None => (Pos::SECTION_MINIMUM_DETAIL_LEVEL as u8..=DetailLevel::MAX as u8)
+            .filter_map(|l| DetailLevel::try_new(l).ok())
+            .collect(),
+    };
+
+    let mut scanned = HashSet::new();
+    let mut finer = Vec::new();
+    let mut hits = Vec::new();
+    for level in levels {
+        let mut level_scanned = Vec::new();
+
+        Section::for_each(conn, Some(level), |mut section| {
+            level_scanned.push(section.pos);
+            if let Err(e) = section.decompress() {
+                tracing::warn!("Skipping section {}: {e:#}", section.pos);
+                return Ok(());
+            }
+            hits.extend(
+                section_hits(&section, pattern)
+                    .into_iter()
+                    .filter(|hit| !covered(&scanned, &finer, hit)),
+            );
+            Ok::<_, Error>(())
+        })?;
+
+        if !level_scanned.is_empty() {
+            finer.push(level);
+            scanned.extend(level_scanned);
+        }
+    }
+
+    Ok(hits)
+}
+
+/// Whether the hit lies in a section of one of the finer detail levels.
+fn covered(scanned: &HashSet<Pos>, levels: &[DetailLevel], hit: &Hit) -> bool {
+    levels
+        .iter()
+        .any(|&detail_level| scanned.contains(&Pos::from_block(detail_level, hit.x, hit.z)))
+}
+
+/// Merges hits at most `radius` blocks apart horizontally into locations, most hits first.
+#[inline]
+#[must_use]
+pub fn cluster(hits: &[Hit], radius: i32) -> Vec<Location> {
+    let radius = radius.max(0);
+    // Hits with a gap of at most `radius` are in the same or a neighbouring cell
+    let cell_size = radius + hits.iter().map(|h| h.width).max().unwrap_or(1);
+    let cell = |x: i32, z: i32| (x.div_euclid(cell_size), z.div_euclid(cell_size));
+    let gap = |a_min: i32, a_width: i32, b_min: i32, b_width: i32| {
+        (a_min.max(b_min) - (a_min + a_width).min(b_min + b_width)).max(0)
+    };
+
+    let mut grid: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+    for (i, hit) in hits.iter().enumerate() {
+        grid.entry(cell(hit.x, hit.z)).or_default().push(i);
+    }
+
+    // Union-find over the hit indices
+    let mut parent: Vec<usize> = (0..hits.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    for (i, hit) in hits.iter().enumerate() {
+        let (cx, cz) = cell(hit.x, hit.z);
+        for nx in cx - 1..=cx + 1 {
+            for nz in cz - 1..=cz + 1 {
+                let Some(others) = grid.get(&(nx, nz)) else {
+                    continue;
+                };
+                for &j in others {
+                    let other = &hits[j];
+                    if gap(hit.x, hit.width, other.x, other.width) <= radius
+                        && gap(hit.z, hit.width, other.z, other.width) <= radius
+                    {
+                        let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                        parent[a] = b;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut locations: HashMap<usize, Location> = HashMap::new();
+    for (i, hit) in hits.iter().enumerate() {
+        let hit_location = Location::from(*hit);
+        locations
+            .entry(root(&mut parent, i))
+            .and_modify(|l| {
+                l.min = (
+                    l.min.0.min(hit_location.min.0),
+                    l.min.1.min(hit_location.min.1),
+                    l.min.2.min(hit_location.min.2),
+                );
+                l.max = (
+                    l.max.0.max(hit_location.max.0),
+                    l.max.1.max(hit_location.max.1),
+                    l.max.2.max(hit_location.max.2),
+                );
+                l.hits += 1;
+            })
+            .or_insert(hit_location);
+    }
+
+    let mut locations: Vec<Location> = locations.into_values().collect();
+    locations.sort_unstable_by(|a, b| b.hits.cmp(&a.hits).then(a.min.cmp(&b.min)));
+    locations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(x: i32, z: i32, width: i32) -> Hit {
+        Hit {
+            x,
+            z,
+            width,
+            min_y: 0,
+            max_y: 1,
+        }
+    }
+
+    #[test]
+    fn patterns() {
+        let pattern = Pattern::block("oak_log[ axis = y ,]").unwrap();
+        assert_eq!(pattern.block.as_deref(), Some("minecraft:oak_log"));
+        assert_eq!(pattern.states, [("axis".to_string(), "y".to_string())]);
+        assert_eq!(
+            Pattern::block("mod:ore").unwrap().block.as_deref(),
+            Some("mod:ore")
+        );
+        assert!(
+            Pattern::biome("plains")
+                .with_biome("desert")
+                .block
+                .is_none()
+        );
+        assert!(Pattern::default().is_empty());
+
+        for invalid in ["", "[axis=y]", "oak_log[axis=y", "oak_log[axis]"] {
+            assert!(Pattern::block(invalid).is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn finer_sections_cover_hits() {
+        let scanned = HashSet::from([Pos::new(DetailLevel::Chunk4, -1, 0)]);
+        let levels = [DetailLevel::Chunk4];
+
+        assert!(covered(&scanned, &levels, &hit(-64, 0, 2)));
+        assert!(covered(&scanned, &levels, &hit(-1, 63, 2)));
+        assert!(!covered(&scanned, &levels, &hit(0, 0, 2)));
+        assert!(!covered(&scanned, &levels, &hit(-1, -1, 2)));
+        assert!(!covered(&scanned, &[], &hit(-1, 0, 2)));
+    }
+
+    #[test]
+    fn cluster_across_cells() {
+        // Cells are 3 blocks wide, the first two hits are in neighbouring cells 2 blocks apart
+        let locations = cluster(&[hit(2, 0, 1), hit(5, 0, 1), hit(9, 0, 1)], 2);
+        assert_eq!(
+            locations,
+            [
+                Location {
+                    min: (2, 0, 0),
+                    max: (5, 0, 0),
+                    hits: 2,
+                },
+                Location {
+                    min: (9, 0, 0),
+                    max: (9, 0, 0),
+                    hits: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn cluster_transitively() {
+        // The outer hits are too far apart but both are close to the middle one
+        let locations = cluster(&[hit(6, 6, 1), hit(0, 0, 1), hit(3, 3, 1)], 2);
+        assert_eq!(
+            locations,
+            [Location {
+                min: (0, 0, 0),
+                max: (6, 0, 6),
+                hits: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn cluster_negative_coordinates() {
+        let hits = [hit(-1, -1, 1), hit(1, 1, 1), hit(-4, 0, 1)];
+        let locations = cluster(&hits, 1);
+        assert_eq!(
+            locations,
+            [
+                Location {
+                    min: (-1, 0, -1),
+                    max: (1, 0, 1),
+                    hits: 2,
+                },
+                Location {
+                    min: (-4, 0, 0),
+                    max: (-4, 0, 0),
+                    hits: 1,
+                },
+            ]
+        );
+
+        // Touching columns are merged even without a radius
+        assert_eq!(cluster(&[hit(-4, 0, 4), hit(0, -4, 4)], 0).len(), 1);
+        assert_eq!(cluster(&[hit(-5, 0, 4), hit(0, -4, 4)], 0).len(), 2);
+        assert!(cluster(&[], 8).is_empty());
+    }
+}
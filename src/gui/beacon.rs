@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+
+use super::{Hotkeys, duck::DuckDb, marker::spawn_marker};
+use crate::beacon::BeaconBeam;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BeaconPlugin;
+
+impl Plugin for BeaconPlugin {
+    #[inline]
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, load.in_set(Hotkeys));
+    }
+}
+
+fn load(
+    mut commands: Commands,
+    kb_input: Res<ButtonInput<KeyCode>>,
+    db: Res<DuckDb>,
+    old_beacons: Query<Entity, With<BeaconBeam>>,
+) {
+    if !kb_input.just_pressed(KeyCode::KeyL) {
+        return;
+    }
+
+    let conn = db.lock();
+    // Older databases don't have the table yet
+    let beacons = match BeaconBeam::get_all(&conn) {
+        Ok(beacons) => beacons,
+        Err(e) => {
+            bevy::log::warn!("Failed to load beacon beams: {e:#}");
+            return;
+        }
+    };
+    drop(conn);
+    bevy::log::info!("Found {} beacon beams", beacons.len());
+
+    for entity in &old_beacons {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for beacon in beacons {
+        spawn_marker(&mut commands, beacon.x, beacon.z, beacon.color).insert(beacon);
+    }
+}
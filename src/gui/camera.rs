@@ -20,7 +20,7 @@ impl Plugin for CameraPlugin {
                 ..Default::default()
             },
         ));
-        app.add_systems(Update, (move_camera, zoom_camera));
+        app.add_systems(Update, (move_camera, zoom_camera).in_set(super::Hotkeys));
     }
 }
 
@@ -54,10 +54,6 @@ pub fn move_camera(
         direction += camera.right().truncate();
     }
 
-    if kb_input.pressed(KeyCode::Numpad1) {
-        camera.translation = Vec3::new(-2300., -2000., 0.);
-    }
-
     let accel = direction.normalize_or_zero() * CAMERA_SPEED;
 
     *cam_speed += accel * 0.3 * time.delta_secs();
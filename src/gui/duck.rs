@@ -1,53 +1,23 @@
 use std::sync::{Arc, Mutex};
 
-use anyhow::Result;
+use crate::backend::Connection;
 use bevy::prelude::*;
-use duckdb::Connection;
-
-#[derive(Debug, Clone, Copy, Default)]
-pub struct DuckPlugin;
-
-impl Plugin for DuckPlugin {
-    #[inline]
-    fn build(&self, app: &mut App) {
-        app.init_resource::<DuckDb>();
-    }
-}
 
 #[derive(Debug, Clone, Resource)]
 pub struct DuckDb {
     conn: Arc<Mutex<Connection>>,
 }
 
-impl Default for DuckDb {
+impl DuckDb {
     #[inline]
-    fn default() -> Self {
+    pub fn new(conn: Connection) -> Self {
         Self {
-            conn: Arc::new(Mutex::new(Connection::open_in_memory().unwrap())),
+            conn: Arc::new(Mutex::new(conn)),
         }
     }
-}
 
-impl DuckDb {
     #[inline]
     pub fn lock(&self) -> impl std::ops::DerefMut<Target = Connection> {
         self.conn.lock().unwrap()
     }
-
-    #[inline]
-    pub fn attach_distant_horizons(&self, dh_path: impl AsRef<str>) -> Result<()> {
-        let conn = self.lock();
-        conn.execute("INSTALL SQLITE", [])?;
-        conn.execute(
-            &format!(
-                "ATTACH '{}' AS dh (TYPE SQLITE, READONLY)",
-                dh_path.as_ref()
-            ),
-            [],
-        )?;
-        conn.execute("SET sqlite_all_varchar=true", [])?;
-        conn.execute("USE dh", [])?;
-
-        Ok(())
-    }
 }
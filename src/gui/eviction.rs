@@ -0,0 +1,227 @@
+use std::{collections::BTreeSet, time::Duration};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+
+use super::{
+    duck::DuckDb,
+    section::{Compact, Decompressed, Visible},
+};
+use crate::{Section, section::pos::Pos};
+
+const DEFAULT_BUDGET_MIB: usize = 2048;
+
+/// Memory held by the texture of a single section.
+const IMAGE_BYTES: usize = Section::WIDTH * Section::WIDTH * 4;
+
+/// Sections with whether they were visible last frame.
+type Tracked<'a> = (Entity, &'a Pos, Option<&'a mut LastSeen>, Has<Visible>);
+
+/// Sections with what they hold: a texture, and decompressed data unless compact.
+type Evictable<'a> = (
+    Entity,
+    &'a mut Section<'static>,
+    &'a LastSeen,
+    Has<Visible>,
+    Has<Sprite>,
+    Has<Compact>,
+);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EvictionPlugin;
+
+impl Plugin for EvictionPlugin {
+    #[inline]
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MemoryBudget>()
+            .init_resource::<Evicted>()
+            .add_systems(
+                Update,
+                (
+                    track_visibility,
+                    restore,
+                    enforce_budget.run_if(on_timer(Duration::from_secs(1))),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Upper bound for the memory held by loaded sections and their textures, in bytes.
+///
+/// Read from the `DH_MEMORY_BUDGET` env variable in MiB.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct MemoryBudget(pub usize);
+
+impl Default for MemoryBudget {
+    #[inline]
+    fn default() -> Self {
+        let mib = std::env::var("DH_MEMORY_BUDGET")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_BUDGET_MIB);
+        Self(mib * 1024 * 1024)
+    }
+}
+
+/// Time the section was last inside the viewport.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct LastSeen(Duration);
+
+/// Sections despawned to stay within the budget, reloaded once they come back into view.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct Evicted(BTreeSet<Pos>);
+
+impl Evicted {
+    #[inline]
+    pub fn remove(&mut self, pos: &Pos) -> bool {
+        self.0.remove(pos)
+    }
+}
+
+/// Visible part of the map in world coordinates, with some margin to avoid thrashing at the edges.
+fn viewport(camera: &GlobalTransform, projection: &OrthographicProjection) -> Rect {
+    let center = camera.translation().truncate();
+    let view = Rect {
+        min: projection.area.min + center,
+        max: projection.area.max + center,
+    };
+    view.inflate(view.width().max(view.height()) / 4.)
+}
+
+/// Area covered by a section in world coordinates.
+fn section_rect(pos: &Pos) -> Rect {
+    let width = pos.detail_level.block_width() as f32;
+    let x = pos.min_x() as f32;
+    let y = -(pos.min_z() as f32);
+    Rect::new(x, y, x + width, y - width)
+}
+
+fn track_visibility(
+    mut commands: Commands,
+    time: Res<Time>,
+    camera: Single<(&GlobalTransform, &OrthographicProjection), With<Camera2d>>,
+    mut sections: Query<Tracked, With<Section<'static>>>,
+) {
+    let (transform, projection) = *camera;
+    let view = viewport(transform, projection);
+    let now = time.elapsed();
+
+    for (entity, pos, last_seen, was_visible) in &mut sections {
+        let visible = !view.intersect(section_rect(pos)).is_empty();
+
+        match (visible, last_seen) {
+            (true, Some(mut last_seen)) => last_seen.0 = now,
+            (true, None) => {
+                commands.entity(entity).insert(LastSeen(now));
+            }
+            (false, None) => {
+                commands.entity(entity).insert(LastSeen::default());
+            }
+            (false, Some(_)) => {}
+        }
+
+        if visible && !was_visible {
+            commands.entity(entity).insert(Visible);
+        } else if !visible && was_visible {
+            commands.entity(entity).remove::<Visible>();
+        }
+    }
+}
+
+/// Drops data of the least recently seen sections until the budget is met.
+///
+/// Off-screen sections first lose their decompressed data and keep their texture,
+/// on the next pass they are despawned with their texture.
+///
+/// Visible sections stay fully resident. Dropping their compressed buffers would save little
+/// and leave nothing to restore the data from once they go off-screen, short of the database.
+fn enforce_budget(
+    mut commands: Commands,
+    budget: Res<MemoryBudget>,
+    mut evicted: ResMut<Evicted>,
+    mut sections: Query<Evictable>,
+) {
+    let mut total: usize = sections
+        .iter()
+        .map(|(_, section, _, _, textured, _)| {
+            section.memory_usage() + if textured { IMAGE_BYTES } else { 0 }
+        })
+        .sum();
+
+    if total <= budget.0 {
+        return;
+    }
+    bevy::log::debug!(
+        "Sections use {} MiB, budget is {} MiB",
+        total / 1024 / 1024,
+        budget.0 / 1024 / 1024
+    );
+
+    let mut lru: Vec<_> = sections
+        .iter()
+        .map(|(entity, _, last_seen, ..)| (last_seen.0, entity))
+        .collect();
+    lru.sort_unstable();
+
+    for (_, entity) in lru {
+        if total <= budget.0 {
+            break;
+        }
+        let Ok((_, mut section, _, visible, textured, compact)) = sections.get_mut(entity) else {
+            continue;
+        };
+        if visible {
+            continue;
+        }
+        let before = section.memory_usage();
+
+        if textured && !compact && section.is_decompressed() && section.is_compressed() {
+            section.bypass_change_detection().drop_decompressed();
+            total -= before - section.memory_usage();
+            commands
+                .entity(entity)
+                .insert(Compact)
+                .remove::<Decompressed>();
+        } else {
+            total = total.saturating_sub(before + if textured { IMAGE_BYTES } else { 0 });
+            evicted.0.insert(section.pos);
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn restore(
+    mut commands: Commands,
+    db: Res<DuckDb>,
+    camera: Single<(&GlobalTransform, &OrthographicProjection), With<Camera2d>>,
+    mut evicted: ResMut<Evicted>,
+) {
+    if evicted.0.is_empty() {
+        return;
+    }
+
+    let (transform, projection) = *camera;
+    let view = viewport(transform, projection);
+
+    let back: Vec<Pos> = evicted
+        .0
+        .iter()
+        .filter(|pos| !view.intersect(section_rect(pos)).is_empty())
+        .copied()
+        .collect();
+    if back.is_empty() {
+        return;
+    }
+
+    let conn = db.lock();
+    for pos in back {
+        evicted.0.remove(&pos);
+        match Section::get(&conn, pos) {
+            Ok(Some(s)) => {
+                commands.spawn((s.transform_2d(), s.pos, s));
+            }
+            Ok(None) => {}
+            Err(e) => bevy::log::error!("Failed to reload section {pos}: {e:#}"),
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use super::{
+    Hotkeys, LOD_LEVEL,
+    marker::{MARKER_Z, ScreenSized},
+};
+use crate::{DetailLevel, section::pos::Pos};
+
+/// Grids with lines closer than this many pixels are not drawn.
+const MIN_LINE_SPACING: f32 = 4.;
+/// Grids with lines closer than this many pixels are not labelled.
+const MIN_LABEL_SPACING: f32 = 96.;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GridPlugin;
+
+impl Plugin for GridPlugin {
+    #[inline]
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Grids>().add_systems(
+            Update,
+            (toggle.in_set(Hotkeys), (draw, update_labels)).chain(),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Grid {
+    Chunk,
+    Region,
+    /// DH sections at the currently displayed detail level
+    Section,
+}
+
+impl Grid {
+    /// Ordered from fine to coarse for the default detail level
+    const ALL: [Self; 3] = [Self::Chunk, Self::Section, Self::Region];
+
+    #[inline]
+    const fn detail_level(self) -> DetailLevel {
+        match self {
+            Self::Chunk => DetailLevel::Chunk,
+            Self::Region => DetailLevel::Region,
+            Self::Section => LOD_LEVEL,
+        }
+    }
+
+    #[inline]
+    const fn key(self) -> KeyCode {
+        match self {
+            Self::Chunk => KeyCode::F1,
+            Self::Region => KeyCode::F2,
+            Self::Section => KeyCode::F3,
+        }
+    }
+
+    #[inline]
+    fn color(self) -> Color {
+        match self {
+            Self::Chunk => Color::srgba(1., 1., 1., 0.3),
+            Self::Region => Color::srgba(1., 0.8, 0.2, 0.8),
+            Self::Section => Color::srgba(0.2, 0.8, 1., 0.6),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Axis {
+    X,
+    Z,
+}
+
+/// Enabled grid overlays.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct Grids(HashSet<Grid>);
+
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct GridLabel;
+
+fn toggle(kb_input: Res<ButtonInput<KeyCode>>, mut grids: ResMut<Grids>) {
+    for grid in Grid::ALL {
+        if kb_input.just_pressed(grid.key()) && !grids.0.remove(&grid) {
+            grids.0.insert(grid);
+        }
+    }
+}
+
+/// Visible part of the map in world coordinates.
+fn viewport(camera: &GlobalTransform, projection: &OrthographicProjection) -> Rect {
+    let center = camera.translation().truncate();
+    Rect {
+        min: projection.area.min + center,
+        max: projection.area.max + center,
+    }
+}
+
+/// Section positions at the detail level of the grid covering the view, in x and z direction.
+fn visible_range(
+    detail_level: DetailLevel,
+    view: Rect,
+) -> (
+    core::ops::RangeInclusive<i32>,
+    core::ops::RangeInclusive<i32>,
+) {
+    let width = detail_level.block_width() as f32;
+    let x = (view.min.x / width).floor() as i32..=(view.max.x / width).ceil() as i32;
+    // The map is drawn with +z pointing down
+    let z = (-view.max.y / width).floor() as i32..=(-view.min.y / width).ceil() as i32;
+    (x, z)
+}
+
+fn draw(
+    mut gizmos: Gizmos,
+    grids: Res<Grids>,
+    camera: Single<(&GlobalTransform, &OrthographicProjection), With<Camera2d>>,
+) {
+    let (transform, projection) = *camera;
+    let view = viewport(transform, projection);
+
+    for grid in &grids.0 {
+        let detail_level = grid.detail_level();
+        if (detail_level.block_width() as f32) / projection.scale < MIN_LINE_SPACING {
+            continue;
+        }
+
+        let (xs, zs) = visible_range(detail_level, view);
+        for x in xs {
+            let x = Pos::new(detail_level, x, 0).min_x() as f32;
+            gizmos.line_2d(
+                Vec2::new(x, view.min.y),
+                Vec2::new(x, view.max.y),
+                grid.color(),
+            );
+        }
+        for z in zs {
+            let y = -(Pos::new(detail_level, 0, z).min_z() as f32);
+            gizmos.line_2d(
+                Vec2::new(view.min.x, y),
+                Vec2::new(view.max.x, y),
+                grid.color(),
+            );
+        }
+    }
+}
+
+/// Labels grid lines with their block coordinate along the top and left edge of the view.
+fn update_labels(
+    mut commands: Commands,
+    grids: Res<Grids>,
+    camera: Single<(&GlobalTransform, &OrthographicProjection), With<Camera2d>>,
+    mut labels: Local<HashMap<(Axis, i32), Entity>>,
+    mut transforms: Query<&mut Transform, With<GridLabel>>,
+) {
+    let (transform, projection) = *camera;
+    let view = viewport(transform, projection);
+    let margin = 2. * projection.scale;
+
+    let mut wanted = HashSet::new();
+    // Coarser grids first, lines shared with finer grids get their label
+    for grid in Grid::ALL.iter().rev().filter(|g| grids.0.contains(g)) {
+        let detail_level = grid.detail_level();
+        if (detail_level.block_width() as f32) / projection.scale < MIN_LABEL_SPACING {
+            continue;
+        }
+
+        let (xs, zs) = visible_range(detail_level, view);
+        let xs = xs.map(|x| {
+            let x = Pos::new(detail_level, x, 0).min_x();
+            let at = Vec3::new(x as f32 + margin, view.max.y - margin, MARKER_Z + 1.);
+            ((Axis::X, x), at, format!("x {x}"))
+        });
+        let zs = zs.map(|z| {
+            let z = Pos::new(detail_level, 0, z).min_z();
+            let at = Vec3::new(view.min.x + margin, -(z as f32) - margin, MARKER_Z + 1.);
+            ((Axis::Z, z), at, format!("z {z}"))
+        });
+
+        for (key, at, text) in xs.chain(zs) {
+            if !wanted.insert(key) {
+                continue;
+            }
+            if let Some(mut transform) = labels
+                .get(&key)
+                .and_then(|label| transforms.get_mut(*label).ok())
+            {
+                transform.translation = at;
+                continue;
+            }
+
+            let label = commands
+                .spawn((
+                    GridLabel,
+                    ScreenSized,
+                    Text2d::new(text),
+                    TextFont {
+                        font_size: 12.,
+                        ..default()
+                    },
+                    TextColor(grid.color().with_alpha(1.)),
+                    Anchor::TopLeft,
+                    Transform::from_translation(at),
+                ))
+                .id();
+            labels.insert(key, label);
+        }
+    }
+
+    labels.retain(|key, label| {
+        let keep = wanted.contains(key);
+        if !keep {
+            commands.entity(*label).despawn();
+        }
+        keep
+    });
+}
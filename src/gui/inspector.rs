@@ -0,0 +1,161 @@
+use core::fmt::Write;
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use super::section::Compact;
+use crate::{Section, block::Block, section::columns};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    #[inline]
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Inspected>()
+            .add_systems(Startup, spawn_panel)
+            .add_systems(Update, (pick, update_panel).chain());
+    }
+}
+
+/// Block coordinates (x, z) of the column shown in the inspector panel.
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub struct Inspected(pub Option<(i32, i32)>);
+
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct InspectorPanel;
+
+fn spawn_panel(mut commands: Commands) {
+    commands.spawn((
+        InspectorPanel,
+        Text::default(),
+        TextFont {
+            font_size: 14.,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.),
+            right: Val::Px(8.),
+            padding: UiRect::all(Val::Px(8.)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0., 0., 0., 0.75)),
+        Visibility::Hidden,
+    ));
+}
+
+/// Left click selects the column under the cursor, right click closes the panel.
+fn pick(
+    mouse: Res<ButtonInput<MouseButton>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut inspected: ResMut<Inspected>,
+) {
+    if mouse.just_pressed(MouseButton::Right) {
+        inspected.0 = None;
+        return;
+    }
+
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let (camera, transform) = *camera;
+    let Ok(world) = camera.viewport_to_world_2d(transform, cursor) else {
+        return;
+    };
+
+    // The map is drawn with +z pointing down
+    inspected.0 = Some((world.x.floor() as i32, (-world.y).floor() as i32));
+}
+
+fn update_panel(
+    mut commands: Commands,
+    inspected: Res<Inspected>,
+    sections: Query<(Entity, &Section<'static>, Has<Compact>)>,
+    changed: Query<(), Changed<Section<'static>>>,
+    panel: Single<(&mut Text, &mut Visibility), With<InspectorPanel>>,
+) {
+    let (mut text, mut visibility) = panel.into_inner();
+
+    let Some((x, z)) = inspected.0 else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    if !inspected.is_changed() && changed.is_empty() {
+        return;
+    }
+
+    *visibility = Visibility::Visible;
+
+    let section = sections.iter().find(|(_, s, _)| s.pos.contains_block(x, z));
+
+    text.0 = match section {
+        Some((entity, section, compact)) => {
+            if compact {
+                // Decompressed data was dropped to save memory, bring it back
+                commands.entity(entity).remove::<Compact>();
+            }
+            describe_column(section, x, z)
+        }
+        None => format!("x {x}, z {z}\nno section loaded"),
+    };
+}
+
+fn describe_column(section: &Section, x: i32, z: i32) -> String {
+    let width = section.width();
+    // The section was picked because it contains the block
+    let (dx, dz) = columns::from_world(section.pos, x, z).unwrap_or_default();
+    let (col_x, col_z) = columns::to_world(section.pos, dx, dz).unwrap_or((x, z));
+
+    let mut out = String::new();
+    let _ = writeln!(out, "x {x}, z {z}");
+    let _ = writeln!(
+        out,
+        "section {} column [{dx}, {dz}] ({width}x{width} blocks at {col_x}, {col_z})",
+        section.pos
+    );
+
+    let (Some(cols), Some(mapping)) = (section.column_data(), section.mapping()) else {
+        let _ = write!(out, "not decompressed yet");
+        return out;
+    };
+
+    if let Some(steps) = section.world_gen_step() {
+        let _ = write!(out, "gen step: {}", steps.get(dx, dz).as_ref());
+    }
+    if let Some(compression) = section.world_compression() {
+        let _ = write!(out, ", compression: {:?}", compression.get(dx, dz));
+    }
+    let _ = writeln!(out);
+
+    for point in cols.get(dx, dz).iter() {
+        let entry = &mapping[point];
+        let min_y = section.min_y + i32::from(point.min_y());
+        let max_y = min_y + i32::from(point.height());
+
+        let _ = write!(out, "\ny {min_y}..{max_y} {}", entry.full_identifier());
+        let mut states = entry.states().peekable();
+        if states.peek().is_some() {
+            let _ = write!(out, " [");
+            for (i, (key, value)) in states.enumerate() {
+                let sep = if i == 0 { "" } else { ", " };
+                let _ = write!(out, "{sep}{key}={value}");
+            }
+            let _ = write!(out, "]");
+        }
+        let _ = write!(
+            out,
+            "\n    biome {}, sky light {}, block light {}",
+            entry.biome(),
+            point.sky_light() as u8,
+            point.block_light() as u8
+        );
+    }
+
+    out
+}
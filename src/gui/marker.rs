@@ -0,0 +1,68 @@
+use bevy::{ecs::system::EntityCommands, prelude::*, sprite::Anchor};
+
+/// Height of markers drawn on top of the map.
+pub const MARKER_Z: f32 = 500.;
+
+/// Size of a marker on screen in pixels.
+const MARKER_SIZE: f32 = 10.;
+
+/// Keeps an entity the same size on screen regardless of the camera zoom.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct ScreenSized;
+
+pub fn scale_screen_sized(
+    camera: Single<&OrthographicProjection, With<Camera2d>>,
+    mut markers: Query<&mut Transform, With<ScreenSized>>,
+) {
+    let scale = Vec3::splat(camera.scale);
+    for mut transform in &mut markers {
+        transform.scale = scale;
+    }
+}
+
+/// Spawns a square marker with a black outline at the given block position.
+pub fn spawn_marker<'a>(
+    commands: &'a mut Commands,
+    x: i32,
+    z: i32,
+    (r, g, b): (u8, u8, u8),
+) -> EntityCommands<'a> {
+    let mut marker = commands.spawn((
+        Sprite::from_color(Color::BLACK, Vec2::splat(MARKER_SIZE + 2.)),
+        Transform::from_xyz(x as f32 + 0.5, -(z as f32 + 0.5), MARKER_Z),
+        ScreenSized,
+    ));
+    marker.with_children(|parent| {
+        parent.spawn((
+            Sprite::from_color(Color::srgb_u8(r, g, b), Vec2::splat(MARKER_SIZE)),
+            Transform::from_xyz(0., 0., 0.1),
+        ));
+    });
+    marker
+}
+
+/// Spawns a marker with a text label above it.
+pub fn spawn_labelled_marker<'a>(
+    commands: &'a mut Commands,
+    x: i32,
+    z: i32,
+    color: (u8, u8, u8),
+    label: impl Into<String>,
+) -> EntityCommands<'a> {
+    let (r, g, b) = color;
+    let label = label.into();
+    let mut marker = spawn_marker(commands, x, z, color);
+    marker.with_children(|parent| {
+        parent.spawn((
+            Text2d::new(label),
+            TextFont {
+                font_size: 14.,
+                ..default()
+            },
+            TextColor(Color::srgb_u8(r, g, b)),
+            Anchor::BottomCenter,
+            Transform::from_xyz(0., MARKER_SIZE, 0.2),
+        ));
+    });
+    marker
+}
@@ -1,5 +1,11 @@
+mod beacon;
 mod camera;
 mod duck;
+mod eviction;
+mod grid;
+mod inspector;
+mod marker;
+mod navigation;
 mod section;
 
 use bevy::{
@@ -9,27 +15,46 @@ use bevy::{
 
 use crate::DetailLevel;
 
-pub fn main() {
+/// Detail level of the sections shown on the map.
+const LOD_LEVEL: DetailLevel = DetailLevel::Chunk16;
+
+/// Opens the map viewer on the DH database at `db_path`.
+pub fn main(db_path: impl AsRef<std::path::Path>) -> crate::Result<()> {
+    let conn = crate::open_database(db_path, crate::database::Mode::ReadOnly)?;
+
     App::new()
+        .insert_resource(duck::DuckDb::new(conn))
         .add_plugins((
             DefaultPlugins.set(ImagePlugin::default_nearest()),
             FrameTimeDiagnosticsPlugin,
             LogDiagnosticsPlugin::default(),
             camera::CameraPlugin,
-            duck::DuckPlugin,
+            inspector::InspectorPlugin,
+            beacon::BeaconPlugin,
+            eviction::EvictionPlugin,
+            navigation::NavigationPlugin,
+            grid::GridPlugin,
             // Wireframe2dPlugin,
         ))
-        .add_systems(Startup, attach_distant_horizons)
-        .add_systems(Update, (exit, section::update, load))
+        .configure_sets(Update, Hotkeys.run_if(navigation::not_typing))
+        .add_systems(
+            Update,
+            (
+                (exit, load).in_set(Hotkeys),
+                section::update,
+                marker::scale_screen_sized,
+            ),
+        )
         .add_systems(FixedUpdate, (section::decompress, section::texturing))
         .run();
-}
 
-fn attach_distant_horizons(db: Res<duck::DuckDb>) {
-    let dh_path = std::env::var("DH_PATH").unwrap_or_else(|_| "DistantHorizons.sqlite".to_string());
-    db.attach_distant_horizons(dh_path).unwrap();
+    Ok(())
 }
 
+/// Systems reacting to keyboard shortcuts, disabled while typing into a prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+struct Hotkeys;
+
 fn exit(kb_input: Res<ButtonInput<KeyCode>>, mut app_exit_events: ResMut<Events<AppExit>>) {
     if kb_input.just_pressed(KeyCode::Escape) {
         app_exit_events.send(AppExit::Success);
@@ -44,11 +69,11 @@ fn load(
     mut commands: Commands,
     kb_input: Res<ButtonInput<KeyCode>>,
     db: Res<duck::DuckDb>,
+    mut evicted: ResMut<eviction::Evicted>,
     mut last_load: Local<i64>,
     old_sections: Query<(Entity, &crate::section::pos::Pos)>,
 ) {
     use core::mem::take;
-    const LOD_LEVEL: DetailLevel = DetailLevel::Chunk16;
     if !kb_input.just_pressed(KeyCode::KeyL) {
         return;
     }
@@ -70,6 +95,7 @@ fn load(
             old.insert(take(s));
             false
         } else {
+            evicted.remove(&s.pos);
             true
         }
     });
@@ -0,0 +1,393 @@
+use std::path::PathBuf;
+
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+
+use super::{Hotkeys, marker::spawn_labelled_marker};
+use crate::waypoint::{self, Waypoint};
+
+const BOOKMARK_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+const BOOKMARK_COLOR: (u8, u8, u8) = (255, 255, 255);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    #[inline]
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Prompt>()
+            .init_resource::<Bookmarks>()
+            .add_systems(Startup, (spawn_panels, import_waypoints))
+            .add_systems(
+                Update,
+                (
+                    (open_prompt, jump_to_bookmark, toggle_bookmark_list).in_set(Hotkeys),
+                    edit_prompt.after(Hotkeys),
+                    update_prompt_panel.after(edit_prompt),
+                    (draw_bookmarks, update_bookmark_panel)
+                        .after(edit_prompt)
+                        .run_if(resource_changed::<Bookmarks>),
+                ),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PromptKind {
+    GoTo,
+    Bookmark,
+}
+
+/// Text input capturing the keyboard, hotkeys are disabled while it is open.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct Prompt(Option<(PromptKind, String)>);
+
+#[inline]
+pub fn not_typing(prompt: Res<Prompt>) -> bool {
+    prompt.0.is_none()
+}
+
+/// Bookmarks persisted in the Xaero's minimap waypoint format.
+///
+/// The file is read from the `DH_BOOKMARKS` env variable, defaults to `bookmarks.txt`.
+/// A file that can't be read or parsed is never overwritten, only a missing one starts empty.
+#[derive(Debug, Clone, Resource)]
+pub struct Bookmarks {
+    path: PathBuf,
+    list: Vec<Waypoint>,
+    /// Why the file isn't saved, set if it couldn't be loaded
+    load_error: Option<String>,
+}
+
+impl Default for Bookmarks {
+    #[inline]
+    fn default() -> Self {
+        let path: PathBuf = std::env::var_os("DH_BOOKMARKS")
+            .unwrap_or_else(|| "bookmarks.txt".into())
+            .into();
+
+        let loaded = match std::fs::read_to_string(&path) {
+            Ok(content) => waypoint::parse_xaero(&content).map_err(|e| format!("{e:#}")),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.to_string()),
+        };
+        let (list, load_error) = match loaded {
+            Ok(list) => (list, None),
+            Err(e) => {
+                bevy::log::error!(
+                    "Failed to load bookmarks from {}, they won't be saved: {e}",
+                    path.display()
+                );
+                (Vec::new(), Some(e))
+            }
+        };
+
+        Self {
+            path,
+            list,
+            load_error,
+        }
+    }
+}
+
+impl Bookmarks {
+    fn save(&self) {
+        if let Some(e) = &self.load_error {
+            bevy::log::warn!(
+                "Not saving bookmarks to {}, it couldn't be loaded: {e}",
+                self.path.display()
+            );
+            return;
+        }
+        if let Err(e) = std::fs::write(&self.path, waypoint::to_xaero(&self.list)) {
+            bevy::log::error!("Failed to save bookmarks to {}: {e}", self.path.display());
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct PromptPanel;
+
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct BookmarkPanel;
+
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct BookmarkMarker;
+
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct WaypointMarker;
+
+fn spawn_panels(mut commands: Commands) {
+    let background = BackgroundColor(Color::srgba(0., 0., 0., 0.75));
+
+    commands.spawn((
+        PromptPanel,
+        Text::default(),
+        TextFont {
+            font_size: 16.,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.),
+            left: Val::Px(8.),
+            padding: UiRect::all(Val::Px(8.)),
+            ..default()
+        },
+        background,
+        Visibility::Hidden,
+    ));
+
+    commands.spawn((
+        BookmarkPanel,
+        Text::default(),
+        TextFont {
+            font_size: 14.,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.),
+            left: Val::Px(8.),
+            padding: UiRect::all(Val::Px(8.)),
+            ..default()
+        },
+        background,
+        Visibility::Hidden,
+    ));
+}
+
+/// Loads the waypoint files listed in the `DH_WAYPOINTS` env variable.
+fn import_waypoints(mut commands: Commands) {
+    let Some(paths) = std::env::var_os("DH_WAYPOINTS") else {
+        return;
+    };
+
+    for path in std::env::split_paths(&paths) {
+        let waypoints = match waypoint::load(&path) {
+            Ok(waypoints) => waypoints,
+            Err(e) => {
+                bevy::log::error!("Failed to import waypoints: {e:#}");
+                continue;
+            }
+        };
+        bevy::log::info!(
+            "Imported {} waypoints from {}",
+            waypoints.len(),
+            path.display()
+        );
+
+        for waypoint in waypoints {
+            spawn_labelled_marker(
+                &mut commands,
+                waypoint.x,
+                waypoint.z,
+                waypoint.color,
+                waypoint.name,
+            )
+            .insert(WaypointMarker);
+        }
+    }
+}
+
+fn open_prompt(kb_input: Res<ButtonInput<KeyCode>>, mut prompt: ResMut<Prompt>) {
+    if kb_input.just_pressed(KeyCode::KeyG) {
+        prompt.0 = Some((PromptKind::GoTo, String::new()));
+    } else if kb_input.just_pressed(KeyCode::KeyB) {
+        prompt.0 = Some((PromptKind::Bookmark, String::new()));
+    }
+}
+
+fn edit_prompt(
+    mut keys: EventReader<KeyboardInput>,
+    mut prompt: ResMut<Prompt>,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut camera: Single<&mut Transform, With<Camera2d>>,
+) {
+    // Don't type the key that opened the prompt
+    if prompt.0.is_none() || prompt.is_changed() {
+        keys.clear();
+        return;
+    }
+
+    for key in keys.read() {
+        if key.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &key.logical_key {
+            Key::Escape => {
+                prompt.0 = None;
+                return;
+            }
+            Key::Enter => {
+                let Some((kind, input)) = prompt.0.take() else {
+                    return;
+                };
+                match kind {
+                    PromptKind::GoTo => go_to(&input, &mut camera),
+                    PromptKind::Bookmark => {
+                        let name = match input.trim() {
+                            "" => format!("Bookmark {}", bookmarks.list.len() + 1),
+                            name => name.to_string(),
+                        };
+                        bookmarks.list.push(Waypoint {
+                            name,
+                            x: camera.translation.x.floor() as i32,
+                            y: None,
+                            z: (-camera.translation.y).floor() as i32,
+                            color: BOOKMARK_COLOR,
+                        });
+                        bookmarks.save();
+                    }
+                }
+                return;
+            }
+            Key::Backspace => {
+                if let Some((_, input)) = &mut prompt.0 {
+                    input.pop();
+                }
+            }
+            Key::Space => {
+                if let Some((_, input)) = &mut prompt.0 {
+                    input.push(' ');
+                }
+            }
+            Key::Character(c) => {
+                if let Some((_, input)) = &mut prompt.0 {
+                    input.push_str(c);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Moves the camera to `x z` or `x y z`, separated by spaces or commas.
+fn go_to(input: &str, camera: &mut Transform) {
+    let coords: Result<Vec<f32>, _> = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(str::parse)
+        .collect();
+
+    match coords.as_deref() {
+        Ok(&[x, z] | &[x, _, z]) => {
+            camera.translation.x = x;
+            camera.translation.y = -z;
+        }
+        _ => bevy::log::warn!("Invalid coordinates: {input:?}"),
+    }
+}
+
+fn jump_to_bookmark(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bookmarks: Res<Bookmarks>,
+    mut camera: Single<&mut Transform, With<Camera2d>>,
+) {
+    let Some(index) = BOOKMARK_KEYS
+        .iter()
+        .position(|key| kb_input.just_pressed(*key))
+    else {
+        return;
+    };
+    let Some(bookmark) = bookmarks.list.get(index) else {
+        return;
+    };
+
+    camera.translation.x = bookmark.x as f32 + 0.5;
+    camera.translation.y = -(bookmark.z as f32 + 0.5);
+}
+
+fn toggle_bookmark_list(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut panel: Single<&mut Visibility, With<BookmarkPanel>>,
+) {
+    if kb_input.just_pressed(KeyCode::KeyK) {
+        **panel = match **panel {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn update_prompt_panel(
+    prompt: Res<Prompt>,
+    panel: Single<(&mut Text, &mut Visibility), With<PromptPanel>>,
+) {
+    if !prompt.is_changed() {
+        return;
+    }
+
+    let (mut text, mut visibility) = panel.into_inner();
+    match &prompt.0 {
+        Some((kind, input)) => {
+            let label = match kind {
+                PromptKind::GoTo => "Go to (x z)",
+                PromptKind::Bookmark => "Bookmark name",
+            };
+            text.0 = format!("{label}: {input}_");
+            *visibility = Visibility::Visible;
+        }
+        None => *visibility = Visibility::Hidden,
+    }
+}
+
+fn update_bookmark_panel(
+    bookmarks: Res<Bookmarks>,
+    mut panel: Single<&mut Text, With<BookmarkPanel>>,
+) {
+    use core::fmt::Write;
+
+    let mut text = String::from("Bookmarks");
+    for (i, bookmark) in bookmarks.list.iter().enumerate() {
+        let key = if i < BOOKMARK_KEYS.len() {
+            format!("{}", i + 1)
+        } else {
+            " ".to_string()
+        };
+        let _ = write!(
+            text,
+            "\n{key} {} ({}, {})",
+            bookmark.name, bookmark.x, bookmark.z
+        );
+    }
+    panel.0 = text;
+}
+
+fn draw_bookmarks(
+    mut commands: Commands,
+    bookmarks: Res<Bookmarks>,
+    old_markers: Query<Entity, With<BookmarkMarker>>,
+) {
+    for entity in &old_markers {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for bookmark in &bookmarks.list {
+        spawn_labelled_marker(
+            &mut commands,
+            bookmark.x,
+            bookmark.z,
+            bookmark.color,
+            bookmark.name.clone(),
+        )
+        .insert(BookmarkMarker);
+    }
+}
@@ -5,11 +5,15 @@ use crate::Section;
 #[derive(Debug, Clone, Copy, Default, Component)]
 pub struct Decompressed;
 
-const PURPLE: (u8, u8, u8) = (255, 0, 255);
-
+/// Section is inside the (slightly enlarged) viewport.
 #[derive(Debug, Clone, Copy, Default, Component)]
 pub struct Visible;
 
+/// Section keeps its texture but its decompressed data was dropped to save memory.
+/// It is only decompressed again on demand.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct Compact;
+
 #[inline]
 pub fn update(
     mut commands: Commands,
@@ -17,15 +21,18 @@ pub fn update(
 ) {
     for (entity, section) in sections.iter() {
         if !section.is_decompressed() {
-            commands.entity(entity).remove::<Decompressed>();
+            commands.entity(entity).remove::<(Decompressed, Compact)>();
         }
     }
 }
 
+/// Sections whose data isn't decompressed and wasn't dropped on purpose.
+type Pending = (Without<Decompressed>, Without<Compact>);
+
 #[inline]
 pub fn decompress(
     commands: ParallelCommands,
-    mut sections: Query<(Entity, &mut Section<'static>), Without<Decompressed>>,
+    mut sections: Query<(Entity, &mut Section<'static>), Pending>,
 ) {
     use std::sync::atomic::{AtomicBool, Ordering};
     use std::time::Instant;
@@ -47,7 +54,7 @@ pub fn decompress(
         }
         bevy::log::debug!("Decompressing section {entity}");
         if let Err(e) = section.decompress() {
-            bevy::log::error!("Failed to decompress section: {e:?}");
+            bevy::log::error!("Failed to decompress section: {e:#}");
         }
         debug_assert!(section.is_decompressed());
         commands.command_scope(|mut c| {
@@ -120,17 +127,10 @@ pub fn texturing(
 }
 
 fn build_section_image(section: &Section) -> Option<Image> {
-    // use smol_str::SmolStr;
-    // use std::collections::BTreeMap;
-    // use std::sync::Mutex;
-    // static BLOCK_CACHE: Mutex<BTreeMap<SmolStr, CachedBlock<SmolStr>>> =
-    //     Mutex::new(BTreeMap::new());
-
-    use crate::block::Block;
-    use bevy::render::render_resource::Extent3d;
-    use bevy::render::render_resource::TextureDescriptor;
-    let mut img = Image {
-        data: vec![0; Section::WIDTH * Section::WIDTH * 4],
+    use bevy::render::render_resource::{Extent3d, TextureDescriptor};
+
+    Some(Image {
+        data: crate::render::section_rgba(section)?,
         texture_descriptor: TextureDescriptor {
             size: Extent3d {
                 width: Section::WIDTH as u32,
@@ -140,74 +140,5 @@ fn build_section_image(section: &Section) -> Option<Image> {
             ..Image::transparent().texture_descriptor
         },
         ..Image::transparent()
-    };
-
-    let cols = section.column_data()?;
-    let mapping = section.mapping()?;
-    let mut is_nether = None;
-    // let mut cache = BLOCK_CACHE.lock().unwrap();
-
-    // let mut cache = BTreeMap::<SmolStr, CachedBlock<SmolStr>>::new();
-
-    for dz in 0..Section::WIDTH as u32 {
-        for dx in 0..Section::WIDTH as u32 {
-            let col = cols[(dz as usize, dx as usize)].as_ref();
-            let slices = col.into_iter().map(|p| {
-                let entry = &mapping[p];
-
-                // let entry = cache
-                //     .entry(entry.block.clone())
-                //     .or_insert_with(|| CachedBlock::new(entry.block.clone()));
-
-                // // if let Some(block) = cache.get(entry.block.as_str()) {
-                // //     return (*p, block.to_owned());
-                // // }
-
-                // // let block = CachedBlock::new(entry.block.clone());
-                // // cache.insert(entry.block.clone(), block.to_owned());
-
-                let block = entry.to_owned();
-
-                (*p, block)
-            });
-
-            let mut above_nether_roof = true;
-            let mut in_or_above_nether_roof = true;
-            for (_dp, b) in slices {
-                if b.is_transparent() {
-                    in_or_above_nether_roof = above_nether_roof;
-                    continue;
-                }
-                above_nether_roof = false;
-
-                let is_nether = is_nether.get_or_insert_with(|| b.in_nether());
-
-                if *is_nether && (above_nether_roof || in_or_above_nether_roof) {
-                    continue;
-                }
-
-                let (r, g, b) = b.map_color().unwrap_or(PURPLE);
-                // let color = Color::srgb_u8(r, g, b);
-
-                let index = (dz * Section::WIDTH as u32 + dx) as usize;
-                let offset = index * 4;
-                // let bytes = &mut img.data[offset..offset + 4];
-
-                // bytes[0] = r;
-                // bytes[1] = g;
-                // bytes[2] = b;
-                // bytes[3] = u8::MAX;
-
-                img.data[offset + 0] = r;
-                img.data[offset + 1] = g;
-                img.data[offset + 2] = b;
-                img.data[offset + 3] = u8::MAX;
-
-                // img.set_color_at(dx, dz, color).unwrap();
-                break;
-            }
-        }
-    }
-
-    Some(img)
+    })
 }
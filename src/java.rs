@@ -1,20 +1,47 @@
 use std::io::BufRead;
 
-use anyhow::Result;
-use simd_cesu8::mutf8::decode as decode_mutf8;
+use simd_cesu8::mutf8::{decode as decode_mutf8, encode as encode_mutf8};
 
 /// https://docs.oracle.com/en/java/javase/21/docs/api/java.base/java/io/DataInput.html#readUTF()
 #[inline]
 #[allow(non_snake_case)]
-pub fn readUTF(mut input: impl BufRead) -> Result<String> {
+pub fn readUTF(mut input: impl BufRead) -> crate::Result<String> {
     let mut len = [0, 0];
-    input.read_exact(&mut len)?;
+    input
+        .read_exact(&mut len)
+        .map_err(|_| crate::Error::format("missing string length"))?;
     let len: usize = u16::from_be_bytes(len).into();
 
     let mut buf = vec![0; len];
-    input.read_exact(&mut buf)?;
+    input
+        .read_exact(&mut buf)
+        .map_err(|_| crate::Error::format("string shorter than its length"))?;
 
-    let utf = decode_mutf8(&buf[..len])?;
+    let utf = decode_mutf8(&buf[..len])
+        .map_err(|_| crate::Error::format("invalid modified UTF-8 string"))?;
 
     Ok(utf.into_owned())
 }
+
+/// https://docs.oracle.com/en/java/javase/21/docs/api/java.base/java/io/DataOutput.html#writeUTF(java.lang.String)
+#[inline]
+#[allow(non_snake_case)]
+pub fn writeUTF(output: &mut Vec<u8>, value: &str) -> crate::Result<()> {
+    let buf = encode_mutf8(value);
+    let len = u16::try_from(buf.len())
+        .map_err(|_| crate::Error::format("string longer than 65535 bytes"))?;
+
+    output.extend_from_slice(&len.to_be_bytes());
+    output.extend_from_slice(&buf);
+    Ok(())
+}
+
+/// https://docs.oracle.com/en/java/javase/21/docs/api/java.base/java/util/Arrays.html#hashCode(byte%5B%5D)
+#[inline]
+#[must_use]
+#[allow(non_snake_case)]
+pub fn hashCode(bytes: &[u8]) -> i32 {
+    bytes.iter().fold(1i32, |hash, &b| {
+        hash.wrapping_mul(31).wrapping_add(i32::from(b as i8))
+    })
+}
@@ -1,14 +1,31 @@
+pub mod backend;
+pub mod beacon;
 pub mod block;
+pub mod census;
 mod compression;
+pub mod database;
 mod detail_level;
+pub mod diff;
+mod error;
+pub mod find;
 #[cfg(feature = "gui")]
 pub mod gui;
 mod java;
 mod light;
-mod repo;
+pub mod propagate;
+pub mod prune;
+pub mod render;
+pub mod repo;
+pub mod schema;
 pub mod section;
+pub mod stats;
+pub mod waypoint;
 
+pub use compression::Compression;
+pub use database::open_database;
 pub use detail_level::DetailLevel;
+pub use error::{Error, Result};
+pub use light::LightLevel;
 pub use section::Section;
 
 pub mod minecraft {
@@ -30,4 +47,61 @@ pub mod minecraft {
 
     pub static MINECRAFT_WORLD: LazyLock<Option<String>> =
         LazyLock::new(|| from_env!("MINECRAFT_WORLD"));
+
+    /// Name of the database file DH keeps for every dimension.
+    pub const DATABASE_FILE: &str = "DistantHorizons.sqlite";
+
+    /// Save folder of [`MINECRAFT_WORLD`] inside [`MINECRAFT_PATH`].
+    #[inline]
+    pub fn world_path() -> Option<PathBuf> {
+        let minecraft = MINECRAFT_PATH.as_ref()?;
+        let world = MINECRAFT_WORLD.as_ref()?;
+        Some(minecraft.join("saves").join(world))
+    }
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Dimension {
+        #[default]
+        Overworld,
+        Nether,
+        End,
+    }
+
+    impl Dimension {
+        /// Folder of the dimension relative to the world save.
+        #[inline]
+        #[must_use]
+        pub const fn folder(self) -> &'static str {
+            match self {
+                Self::Overworld => "",
+                Self::Nether => "DIM-1",
+                Self::End => "DIM1",
+            }
+        }
+
+        /// Location of the DH database of this dimension inside a world save.
+        #[inline]
+        #[must_use]
+        pub fn database_path(self, world: impl AsRef<std::path::Path>) -> PathBuf {
+            world
+                .as_ref()
+                .join(self.folder())
+                .join("data")
+                .join(DATABASE_FILE)
+        }
+    }
+
+    impl core::str::FromStr for Dimension {
+        type Err = crate::Error;
+
+        #[inline]
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_ascii_lowercase().as_str() {
+                "overworld" | "minecraft:overworld" => Ok(Self::Overworld),
+                "nether" | "the_nether" | "minecraft:the_nether" => Ok(Self::Nether),
+                "end" | "the_end" | "minecraft:the_end" => Ok(Self::End),
+                _ => Err(crate::Error::parse(format!("unknown dimension: {s:?}"))),
+            }
+        }
+    }
 }
@@ -25,7 +25,7 @@ impl LightLevel {
 }
 
 impl TryFrom<u8> for LightLevel {
-    type Error = anyhow::Error;
+    type Error = crate::Error;
 
     #[inline]
     fn try_from(value: u8) -> Result<Self, Self::Error> {
@@ -34,7 +34,9 @@ impl TryFrom<u8> for LightLevel {
                 // Safety: value is guaranteed to be a valid light level
                 unsafe { core::mem::transmute::<u8, Self>(v) },
             ),
-            _ => anyhow::bail!("invalid value: {value}"),
+            _ => Err(crate::Error::format(format!(
+                "invalid light level: {value}"
+            ))),
         }
     }
 }
@@ -0,0 +1,374 @@
+//! Finishing the updates DH records in the `ApplyToParent` and `ApplyToChildren` flags.
+//!
+//! DH flags a section when its data changed and updates the other detail levels in the background.
+//! A game closed before that finished leaves parents and children that don't match their data.
+//!
+//! Parents are rebuilt with [`Section::downsample`], which only approximates DH's merge.
+//! The `ApplyToParent` flags stay set, on the flagged sections and the rebuilt parents,
+//! so DH still rebuilds those parents its own way.
+
+use core::cmp::Reverse;
+use std::collections::BTreeSet;
+
+use crate::{
+    DetailLevel, Result, Section,
+    backend::{self, Connection, Row},
+    repo::Repo,
+    schema::Schema,
+    section::{pos::Pos, tree::SectionTree},
+};
+
+/// Selects the sections with a flag set.
+const FLAGGED: &str = "CAST(ApplyToParent AS INTEGER) = 1 OR CAST(ApplyToChildren AS INTEGER) = 1";
+
+/// A section with at least one propagation flag set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flagged {
+    pub pos: Pos,
+    pub apply_to_parent: bool,
+    pub apply_to_children: bool,
+}
+
+impl Repo for Flagged {
+    const SELECTION: &'static str = "DetailLevel, PosX, PosZ, \
+         CAST(ApplyToParent AS INTEGER) AS ApplyToParent, \
+         CAST(ApplyToChildren AS INTEGER) AS ApplyToChildren";
+    const TABLE: &'static str = "FullData";
+
+    type Element<'r> = Flagged;
+
+    #[inline]
+    fn from_row<'r>(row: &'r Row) -> backend::Result<Self::Element<'r>> {
+        let detail_level: DetailLevel = row.get("DetailLevel")?;
+        let apply_to_parent: Option<i64> = row.get("ApplyToParent")?;
+        let apply_to_children: Option<i64> = row.get("ApplyToChildren")?;
+        Ok(Flagged {
+            pos: Pos::new(
+                detail_level + Pos::SECTION_MINIMUM_DETAIL_LEVEL,
+                row.get("PosX")?,
+                row.get("PosZ")?,
+            ),
+            apply_to_parent: apply_to_parent == Some(1),
+            apply_to_children: apply_to_children == Some(1),
+        })
+    }
+}
+
+/// The sections a propagation rewrites.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Propagation {
+    /// Sections with a flag set, their `ApplyToChildren` flags are cleared
+    pub flagged: Vec<Flagged>,
+    /// Existing descendants of sections flagged `ApplyToChildren`, filled from their parents,
+    /// coarsest first
+    pub children: Vec<Pos>,
+    /// Ancestors of sections flagged `ApplyToParent`, rebuilt from their children, finest first.
+    /// Missing ones are created, up to the coarsest detail level in the database.
+    pub parents: Vec<Pos>,
+}
+
+impl Propagation {
+    /// Finds the flagged sections and what updating them would rewrite, without reading any data.
+    pub fn plan(conn: &Connection) -> Result<Self> {
+        Schema::detect(conn)?.check_writable()?;
+
+        struct Q;
+
+        impl crate::repo::Query for Q {
+            fn r#where(&self) -> &str {
+                FLAGGED
+            }
+
+            fn bind_params(
+                &self,
+                _stmt: &mut backend::Statement,
+                _params: (),
+            ) -> backend::Result<()> {
+                Ok(())
+            }
+        }
+
+        let flagged = Flagged::select_vec(conn, &Q, |f| f)?;
+        if flagged.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let tree = SectionTree::load(conn)?;
+        let coarsest = tree
+            .positions()
+            .iter()
+            .map(|p| p.detail_level)
+            .max()
+            .unwrap_or(SectionTree::ROOT_DETAIL_LEVEL);
+
+        let mut children = BTreeSet::new();
+        let mut stack: Vec<_> = flagged
+            .iter()
+            .filter(|f| f.apply_to_children)
+            .map(|f| f.pos)
+            .collect();
+        while let Some(pos) = stack.pop() {
+            for child in pos.children().into_iter().flatten() {
+                if tree.contains(child) && children.insert(child) {
+                    stack.push(child);
+                }
+            }
+        }
+
+        let mut parents = BTreeSet::new();
+        for f in flagged.iter().filter(|f| f.apply_to_parent) {
+            let mut pos = f.pos;
+            while let Some(parent) = pos.parent()
+                && parent.detail_level <= coarsest
+                && parents.insert(parent)
+            {
+                pos = parent;
+            }
+        }
+
+        // `Pos` orders by detail level first
+        Ok(Self {
+            flagged,
+            children: children.into_iter().rev().collect(),
+            parents: parents.into_iter().collect(),
+        })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.flagged.is_empty()
+    }
+
+    /// Rewrites the planned sections and clears the `ApplyToChildren` flags,
+    /// should run inside a [`crate::repo::transaction`].
+    pub fn apply(&self, conn: &Connection) -> Result<()> {
+        let load = |pos| -> Result<Option<Section<'static>>> {
+            let Some(mut section) = Section::get(conn, pos)? else {
+                return Ok(None);
+            };
+            section.decompress()?;
+            Ok(Some(section))
+        };
+
+        // Children first, the parents rebuilt afterwards then see the filled in columns.
+        // Still coarsest first, a parent is read back after it was written itself,
+        // and once for all its children.
+        let mut children = self.children.clone();
+        children.sort_by_key(|pos| (Reverse(pos.detail_level), pos.parent()));
+        for siblings in children.chunk_by(|a, b| a.parent() == b.parent()) {
+            let Some(parent) = siblings[0].parent().map(load).transpose()?.flatten() else {
+                continue;
+            };
+            for &pos in siblings {
+                let Some(child) = load(pos)? else {
+                    continue;
+                };
+
+                let mut section = parent.upsample(&child)?;
+                section.compress()?;
+                section.upsert_into(conn)?;
+            }
+        }
+
+        for &pos in &self.parents {
+            let mut children = [None, None, None, None];
+            for (slot, child) in children
+                .iter_mut()
+                .zip(pos.children().into_iter().flatten())
+            {
+                *slot = load(child)?;
+            }
+            let base = load(pos)?;
+            if children.iter().all(Option::is_none) {
+                continue;
+            }
+
+            let mut section = Section::downsample_partial(
+                pos,
+                children.each_ref().map(Option::as_ref),
+                base.as_ref(),
+            )?;
+            section.compress()?;
+            section.upsert_into(conn)?;
+        }
+
+        let sql = format!(
+            "UPDATE {} SET ApplyToChildren = 0 WHERE CAST(ApplyToChildren AS INTEGER) = 1",
+            Flagged::TABLE
+        );
+        conn.execute(&sql, [])?;
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::section::{
+        builder::{Run, SectionBuilder},
+        mapping::Entry,
+        world_gen_step::WorldGenStep,
+    };
+
+    fn pos(detail_level: DetailLevel, x: i32, z: i32) -> Pos {
+        Pos::new(detail_level, x, z)
+    }
+
+    /// Stone up to `top` in every column, or no data at all, without flags.
+    fn insert(conn: &Connection, pos: Pos, top: Option<i32>) {
+        let mut builder = SectionBuilder::new(pos);
+        if let Some(top) = top {
+            for dx in 0..Section::WIDTH {
+                for dz in 0..Section::WIDTH {
+                    let stone = Entry::new("minecraft:plains", "minecraft:stone");
+                    builder.push(dx, dz, Run::new(stone, -64..top)).unwrap();
+                }
+            }
+        }
+        let mut section = builder.build().unwrap();
+        section.clear_propagation();
+        section.compress().unwrap();
+        section.upsert_into(conn).unwrap();
+    }
+
+    fn flag(conn: &Connection, pos: Pos, column: &str) {
+        let sql = format!(
+            "UPDATE FullData SET {column} = 1 WHERE DetailLevel = ? AND PosX = ? AND PosZ = ?"
+        );
+        let level = pos.detail_level - Pos::SECTION_MINIMUM_DETAIL_LEVEL;
+        conn.execute(&sql, (level, pos.x, pos.z)).unwrap();
+    }
+
+    fn load(conn: &Connection, pos: Pos) -> Section<'static> {
+        let mut section = Section::get(conn, pos).unwrap().unwrap();
+        section.decompress().unwrap();
+        section
+    }
+
+    /// Top of column `(dx, dz)` and its world generation step.
+    fn top(section: &Section<'_>, dx: usize, dz: usize) -> (Option<i32>, WorldGenStep) {
+        let top = section.column_data().unwrap().get(dx, dz).first();
+        (
+            top.map(|p| section.min_y + i32::from(p.min_y()) + i32::from(p.height())),
+            *section.world_gen_step().unwrap().get(dx, dz),
+        )
+    }
+
+    #[test]
+    fn nothing_flagged() {
+        let conn = crate::database::open_in_memory();
+        insert(&conn, pos(DetailLevel::Chunk4, 0, 0), Some(0));
+        let propagation = Propagation::plan(&conn).unwrap();
+        assert!(propagation.is_empty());
+        assert_eq!(propagation, Propagation::default());
+    }
+
+    #[test]
+    fn rebuild_parents() {
+        let conn = crate::database::open_in_memory();
+        let child = pos(DetailLevel::Chunk4, 0, 0);
+        insert(&conn, child, Some(10));
+        insert(&conn, pos(DetailLevel::Chunk4, 1, 0), Some(0));
+        // Stale
+        insert(&conn, pos(DetailLevel::Chunk8, 0, 0), Some(-10));
+        insert(&conn, pos(DetailLevel::Chunk16, 0, 0), Some(-10));
+        flag(&conn, child, "ApplyToParent");
+
+        let propagation = Propagation::plan(&conn).unwrap();
+        let flagged = [Flagged {
+            pos: child,
+            apply_to_parent: true,
+            apply_to_children: false,
+        }];
+        assert_eq!(
+            propagation,
+            Propagation {
+                flagged: flagged.to_vec(),
+                children: Vec::new(),
+                // Up to the coarsest detail level in the database
+                parents: vec![
+                    pos(DetailLevel::Chunk8, 0, 0),
+                    pos(DetailLevel::Chunk16, 0, 0)
+                ],
+            }
+        );
+        propagation.apply(&conn).unwrap();
+
+        let parent = load(&conn, pos(DetailLevel::Chunk8, 0, 0));
+        assert_eq!(top(&parent, 0, 0), (Some(10), WorldGenStep::DownSampled));
+        assert_eq!(top(&parent, 32, 0), (Some(0), WorldGenStep::DownSampled));
+        // The quarter of the missing child is kept
+        assert_eq!(top(&parent, 0, 32), (Some(-10), WorldGenStep::Light));
+        let grandparent = load(&conn, pos(DetailLevel::Chunk16, 0, 0));
+        assert_eq!(
+            top(&grandparent, 0, 0),
+            (Some(10), WorldGenStep::DownSampled)
+        );
+        assert_eq!(
+            top(&grandparent, 16, 0),
+            (Some(0), WorldGenStep::DownSampled)
+        );
+        assert_eq!(
+            top(&grandparent, 0, 16),
+            (Some(-10), WorldGenStep::DownSampled)
+        );
+        assert_eq!(top(&grandparent, 32, 32), (Some(-10), WorldGenStep::Light));
+
+        // DH still has to rebuild the parents its own way
+        assert!(parent.apply_to_parent() && grandparent.apply_to_parent());
+        assert_eq!(Propagation::plan(&conn).unwrap().flagged.len(), 3);
+        assert!(load(&conn, child).apply_to_parent());
+    }
+
+    #[test]
+    fn fill_children() {
+        let conn = crate::database::open_in_memory();
+        let flagged = pos(DetailLevel::Chunk16, 0, 0);
+        insert(&conn, flagged, Some(5));
+        for (x, z) in [(0, 0), (1, 0)] {
+            insert(&conn, pos(DetailLevel::Chunk8, x, z), None);
+        }
+        for (x, z) in [(0, 0), (1, 0), (2, 0)] {
+            insert(&conn, pos(DetailLevel::Chunk4, x, z), None);
+        }
+        // Not below the flagged section
+        insert(&conn, pos(DetailLevel::Chunk4, 4, 0), None);
+        flag(&conn, flagged, "ApplyToChildren");
+
+        let propagation = Propagation::plan(&conn).unwrap();
+        assert_eq!(
+            propagation.children,
+            [
+                pos(DetailLevel::Chunk8, 1, 0),
+                pos(DetailLevel::Chunk8, 0, 0),
+                pos(DetailLevel::Chunk4, 2, 0),
+                pos(DetailLevel::Chunk4, 1, 0),
+                pos(DetailLevel::Chunk4, 0, 0),
+            ]
+        );
+        assert!(propagation.parents.is_empty());
+        propagation.apply(&conn).unwrap();
+
+        // The Chunk4 sections are filled from the Chunk8 ones filled before them
+        for &child in &propagation.children {
+            let section = load(&conn, child);
+            assert_eq!(
+                top(&section, 0, 0),
+                (Some(5), WorldGenStep::DownSampled),
+                "{child}"
+            );
+            assert_eq!(
+                top(&section, 63, 63),
+                (Some(5), WorldGenStep::DownSampled),
+                "{child}"
+            );
+        }
+        let section = load(&conn, pos(DetailLevel::Chunk4, 4, 0));
+        assert_eq!(top(&section, 0, 0), (None, WorldGenStep::Empty));
+
+        assert!(!load(&conn, flagged).apply_to_children());
+        assert!(Propagation::plan(&conn).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+//! Deleting the sections a server doesn't need, to shrink the database.
+//!
+//! DH regenerates what's missing from the world when a player comes close again,
+//! so removed sections only cost the time to generate them once more.
+
+use crate::{
+    DetailLevel, Error, Result, Section,
+    backend::{self, Connection, Row},
+    repo::{All, Repo},
+    schema::Schema,
+    section::pos::Pos,
+};
+
+/// The area of a world border, in block coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Border {
+    /// Blocks from `min` to `max`, both included
+    Rectangle {
+        min_x: i32,
+        min_z: i32,
+        max_x: i32,
+        max_z: i32,
+    },
+    /// Blocks at most `radius` away from `(x, z)`
+    Circle { x: i32, z: i32, radius: u32 },
+}
+
+impl Border {
+    /// Whether any block of the section at `pos` is inside the border.
+    #[inline]
+    #[must_use]
+    pub fn overlaps(&self, pos: Pos) -> bool {
+        let width = pos.detail_level.block_width();
+        let (min_x, min_z) = (i64::from(pos.min_x()), i64::from(pos.min_z()));
+        let (max_x, max_z) = (min_x + i64::from(width) - 1, min_z + i64::from(width) - 1);
+
+        match *self {
+            Self::Rectangle {
+                min_x: x1,
+                min_z: z1,
+                max_x: x2,
+                max_z: z2,
+            } => {
+                min_x <= i64::from(x2)
+                    && max_x >= i64::from(x1)
+                    && min_z <= i64::from(z2)
+                    && max_z >= i64::from(z1)
+            }
+            Self::Circle { x, z, radius } => {
+                // Block of the section closest to the center
+                let dx = i64::from(x).clamp(min_x, max_x) - i64::from(x);
+                let dz = i64::from(z).clamp(min_z, max_z) - i64::from(z);
+                dx * dx + dz * dz <= i64::from(radius) * i64::from(radius)
+            }
+        }
+    }
+}
+
+/// Position and modification time of a section, without its data.
+struct Key;
+
+impl Repo for Key {
+    const SELECTION: &'static str = "DetailLevel, PosX, PosZ, \
+         CAST(LastModifiedUnixDateTime AS BIGINT) AS LastModifiedUnixDateTime";
+    const TABLE: &'static str = "FullData";
+
+    type Element<'r> = (Pos, i64);
+
+    #[inline]
+    fn from_row<'r>(row: &'r Row) -> backend::Result<Self::Element<'r>> {
+        let detail_level: DetailLevel = row.get("DetailLevel")?;
+        let pos = Pos::new(
+            detail_level + Pos::SECTION_MINIMUM_DETAIL_LEVEL,
+            row.get("PosX")?,
+            row.get("PosZ")?,
+        );
+        Ok((pos, row.get("LastModifiedUnixDateTime")?))
+    }
+}
+
+/// Which sections to delete, a section meeting any of the conditions is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Prune {
+    /// Only consider sections of this detail level
+    pub detail_level: Option<DetailLevel>,
+    /// Delete the sections entirely outside the border
+    pub outside: Option<Border>,
+    /// Delete the sections of these detail levels
+    pub detail_levels: Vec<DetailLevel>,
+    /// Delete the sections last modified before this Unix time in milliseconds
+    pub modified_before: Option<i64>,
+}
+
+impl Prune {
+    /// Whether no condition is set, nothing would be deleted.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.outside.is_none() && self.detail_levels.is_empty() && self.modified_before.is_none()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn matches(&self, pos: Pos, last_modified: i64) -> bool {
+        if self.detail_level.is_some_and(|l| l != pos.detail_level) {
+            return false;
+        }
+        self.outside.is_some_and(|b| !b.overlaps(pos))
+            || self.detail_levels.contains(&pos.detail_level)
+            || self.modified_before.is_some_and(|t| last_modified < t)
+    }
+
+    /// The positions of the sections to delete, sorted, without reading any data.
+    pub fn plan(&self, conn: &Connection) -> Result<Vec<Pos>> {
+        Schema::detect(conn)?.check_readable()?;
+        if self.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut positions = Vec::new();
+        Key::select_each_with(conn, &All, (), |(pos, last_modified)| {
+            if self.matches(pos, last_modified) {
+                positions.push(pos);
+            }
+            Ok::<_, Error>(())
+        })?;
+        positions.sort_unstable();
+        Ok(positions)
+    }
+
+    /// Deletes the sections at `positions`, returns how many existed.
+    /// Should run inside a [`crate::repo::transaction`].
+    pub fn apply(conn: &Connection, positions: &[Pos]) -> Result<usize> {
+        Schema::detect(conn)?.check_writable()?;
+
+        let mut deleted = 0;
+        for &pos in positions {
+            deleted += usize::from(Section::delete(conn, pos)?);
+        }
+        Ok(deleted)
+    }
+}
+
+/// Unix milliseconds of 2001-09-09, smaller numbers are more likely years or seconds.
+const MIN_MILLIS: i64 = 1_000_000_000_000;
+
+/// Parses a cutoff as Unix milliseconds, or a `YYYY-MM-DD` date at midnight UTC.
+///
+/// Numbers before 2001 are rejected, `2024` or a time in seconds isn't a cutoff in milliseconds.
+#[inline]
+pub fn parse_time(s: &str) -> Result<i64> {
+    let s = s.trim();
+    if let Ok(millis) = s.parse::<i64>() {
+        if millis < MIN_MILLIS {
+            return Err(Error::parse(format!(
+                "{s} is too small for Unix milliseconds, give a date as YYYY-MM-DD"
+            )));
+        }
+        return Ok(millis);
+    }
+
+    let invalid = || Error::parse(format!("invalid date {s:?}, expected YYYY-MM-DD"));
+    let mut parts = s.splitn(3, '-');
+    let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    let year: i64 = year.parse().map_err(|_| invalid())?;
+    let month: i64 = month.parse().map_err(|_| invalid())?;
+    let day: i64 = day.parse().map_err(|_| invalid())?;
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return Err(invalid()),
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return Err(invalid());
+    }
+
+    // Days since 1970-01-01 of the proleptic Gregorian calendar, the year starting in March
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    Ok(days * 86_400_000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_time("1970-01-01").unwrap(), 0);
+        assert_eq!(parse_time("2000-03-01").unwrap(), 951_868_800_000);
+        assert_eq!(parse_time(" 2024-02-29 ").unwrap(), 1_709_164_800_000);
+        assert_eq!(parse_time("2024-12-31").unwrap(), 1_735_603_200_000);
+        assert_eq!(parse_time("1969-12-31").unwrap(), -86_400_000);
+        assert_eq!(parse_time("1717171717171").unwrap(), 1_717_171_717_171);
+
+        for invalid in [
+            "2024-02-30",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-04-31",
+            "2024-13-01",
+            "2024-00-10",
+            "2024-01-00",
+            "2024-01",
+            "2024",
+            "1717171717",
+            "yesterday",
+        ] {
+            assert!(parse_time(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn rectangle() {
+        let border = Border::Rectangle {
+            min_x: -100,
+            min_z: 0,
+            max_x: 0,
+            max_z: 63,
+        };
+        // Chunk4 sections are 64 blocks wide, -1 covers -64..=-1
+        assert!(border.overlaps(Pos::new(DetailLevel::Chunk4, -1, 0)));
+        assert!(border.overlaps(Pos::new(DetailLevel::Chunk4, -2, 0)));
+        assert!(border.overlaps(Pos::new(DetailLevel::Chunk4, 0, 0)));
+        assert!(!border.overlaps(Pos::new(DetailLevel::Chunk4, 1, 0)));
+        assert!(!border.overlaps(Pos::new(DetailLevel::Chunk4, -3, 0)));
+        assert!(!border.overlaps(Pos::new(DetailLevel::Chunk4, -1, -1)));
+        assert!(!border.overlaps(Pos::new(DetailLevel::Chunk4, -1, 1)));
+        // A coarse section around the whole border
+        assert!(border.overlaps(Pos::new(DetailLevel::Region512, -1, 0)));
+    }
+
+    #[test]
+    fn circle() {
+        let border = Border::Circle {
+            x: 0,
+            z: 0,
+            radius: 100,
+        };
+        assert!(border.overlaps(Pos::new(DetailLevel::Chunk4, 0, 0)));
+        // Closest block (64, 64) is about 90.5 away, (128, 0) is 128 away
+        assert!(border.overlaps(Pos::new(DetailLevel::Chunk4, 1, 1)));
+        assert!(!border.overlaps(Pos::new(DetailLevel::Chunk4, 2, 0)));
+        // Closest block (-65, -65) is about 91.9 away, (-129, -65) about 144.5
+        assert!(border.overlaps(Pos::new(DetailLevel::Chunk4, -2, -2)));
+        assert!(!border.overlaps(Pos::new(DetailLevel::Chunk4, -3, -2)));
+
+        let far = Border::Circle {
+            x: 1000,
+            z: -1000,
+            radius: 0,
+        };
+        assert!(far.overlaps(Pos::from_block(DetailLevel::Chunk4, 1000, -1000)));
+        assert!(!far.overlaps(Pos::from_block(DetailLevel::Chunk4, 1064, -1000)));
+    }
+}
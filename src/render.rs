@@ -0,0 +1,45 @@
+use crate::{Section, block::Block};
+
+/// Color of blocks without a known map color.
+const PURPLE: (u8, u8, u8) = (255, 0, 255);
+
+/// Renders the top-down map colors of a decompressed section as RGBA pixels,
+/// one per column, rows running along +z.
+///
+/// Columns without any visible block stay transparent. In the nether the
+/// bedrock roof and everything above it is skipped.
+#[inline]
+#[must_use]
+pub fn section_rgba(section: &Section) -> Option<Vec<u8>> {
+    let mut data = vec![0; Section::WIDTH * Section::WIDTH * 4];
+
+    let cols = section.column_data()?;
+    let mapping = section.mapping()?;
+    let mut is_nether = None;
+
+    for (dx, dz, col) in cols.iter() {
+        let mut above_nether_roof = true;
+        let mut in_or_above_nether_roof = true;
+        for b in col.iter().map(|p| &mapping[p]) {
+            if b.is_transparent() {
+                in_or_above_nether_roof = above_nether_roof;
+                continue;
+            }
+            above_nether_roof = false;
+
+            let is_nether = is_nether.get_or_insert_with(|| b.in_nether());
+
+            if *is_nether && (above_nether_roof || in_or_above_nether_roof) {
+                continue;
+            }
+
+            let (r, g, b) = b.map_color().unwrap_or(PURPLE);
+
+            let offset = (dz * Section::WIDTH + dx) * 4;
+            data[offset..offset + 4].copy_from_slice(&[r, g, b, u8::MAX]);
+            break;
+        }
+    }
+
+    Some(data)
+}
@@ -1,4 +1,4 @@
-use duckdb::{CachedStatement, Connection, Result, Row, Statement};
+use crate::backend::{self, CachedStatement, Connection, Result, Row, Statement};
 
 pub trait Query<P = ()> {
     #[inline]
@@ -11,7 +11,7 @@ pub trait Query<P = ()> {
         None
     }
 
-    fn bind_params(&self, stmt: &mut Statement, params: P);
+    fn bind_params(&self, stmt: &mut Statement, params: P) -> Result<()>;
 
     #[inline]
     fn ordered(self, order: &'static str) -> impl Query<P>
@@ -28,7 +28,7 @@ pub trait Query<P = ()> {
 
 impl<F, P> Query<P> for F
 where
-    F: Fn(&mut Statement, P) + AsRef<str>,
+    F: Fn(&mut Statement, P) -> Result<()> + AsRef<str>,
 {
     #[inline]
     fn r#where(&self) -> &str {
@@ -36,8 +36,8 @@ where
     }
 
     #[inline]
-    fn bind_params(&self, stmt: &mut Statement, params: P) {
-        self(stmt, params);
+    fn bind_params(&self, stmt: &mut Statement, params: P) -> Result<()> {
+        self(stmt, params)
     }
 }
 
@@ -51,7 +51,9 @@ impl Query for All {
     }
 
     #[inline]
-    fn bind_params(&self, _stmt: &mut Statement, _params: ()) {}
+    fn bind_params(&self, _stmt: &mut Statement, _params: ()) -> Result<()> {
+        Ok(())
+    }
 }
 
 struct Ordered<P, Q: Query<P>> {
@@ -72,14 +74,13 @@ impl<P, Q: Query<P>> Query<P> for Ordered<P, Q> {
     }
 
     #[inline]
-    fn bind_params(&self, stmt: &mut Statement, params: P) {
-        self.query.bind_params(stmt, params);
+    fn bind_params(&self, stmt: &mut Statement, params: P) -> Result<()> {
+        self.query.bind_params(stmt, params)
     }
 }
 
 pub trait Repo {
     const SELECTION: &'static str = "*";
-    const INSERT: &'static str;
     const TABLE: &'static str;
 
     type Element<'r>: Sized;
@@ -100,8 +101,7 @@ pub trait Repo {
                 .map_or_else(String::new, |o| format!("ORDER BY {o}"))
         );
         let mut stmt = conn.prepare_cached(&sql)?;
-        query.bind_params(&mut stmt, params);
-        stmt.raw_execute()?;
+        query.bind_params(&mut stmt, params)?;
         Ok(stmt)
     }
 
@@ -112,11 +112,10 @@ pub trait Repo {
         params: P,
         into_owned: impl for<'r> Fn(Self::Element<'r>) -> Self::Element<'static>,
     ) -> Result<Vec<Self::Element<'static>>> {
-        let stmt = Self::prepare_select_with(conn, query, params)?;
+        let mut stmt = Self::prepare_select_with(conn, query, params)?;
 
-        let mut rows = stmt.raw_query();
-        let count = rows.as_ref().map(Statement::row_count).unwrap_or_default();
-        let mut elements = Vec::with_capacity(count);
+        let mut rows = backend::query(&mut stmt)?;
+        let mut elements = Vec::new();
 
         while let Some(row) = rows.next()? {
             elements.push(into_owned(Self::from_row(row)?));
@@ -125,6 +124,24 @@ pub trait Repo {
         Ok(elements)
     }
 
+    /// Calls `f` for every selected element without collecting them first.
+    #[inline]
+    fn select_each_with<Q: Query<P>, P, E: From<backend::Error>>(
+        conn: &Connection,
+        query: &Q,
+        params: P,
+        mut f: impl for<'r> FnMut(Self::Element<'r>) -> core::result::Result<(), E>,
+    ) -> core::result::Result<(), E> {
+        let mut stmt = Self::prepare_select_with(conn, query, params)?;
+
+        let mut rows = backend::query(&mut stmt)?;
+        while let Some(row) = rows.next()? {
+            f(Self::from_row(row)?)?;
+        }
+
+        Ok(())
+    }
+
     #[inline]
     fn select_vec<Q: Query<()>>(
         conn: &Connection,
@@ -134,6 +151,40 @@ pub trait Repo {
         Self::select_vec_with(conn, query, (), into_owned)
     }
 
+    fn from_row<'r>(row: &'r Row) -> Result<Self::Element<'r>>;
+}
+
+/// Runs `f` inside a transaction, committing if it succeeds and rolling back otherwise.
+#[inline]
+pub fn transaction<T, E: From<backend::Error>>(
+    conn: &Connection,
+    f: impl FnOnce() -> core::result::Result<T, E>,
+) -> core::result::Result<T, E> {
+    conn.execute_batch("BEGIN TRANSACTION")?;
+    match f() {
+        Ok(value) => {
+            conn.execute_batch("COMMIT")?;
+            Ok(value)
+        }
+        Err(e) => {
+            // The error of `f` is more useful than a failed rollback
+            let _ = conn.execute_batch("ROLLBACK");
+            Err(e)
+        }
+    }
+}
+
+pub trait WritableRepo: Repo {
+    const INSERT: &'static str;
+
+    /// Identifies a row, the primary key of the table.
+    type Key: Copy;
+
+    fn key(element: &Self::Element<'_>) -> Self::Key;
+
+    /// Selects the row with the given key.
+    fn by_key() -> impl Query<Self::Key>;
+
     #[inline]
     fn insert(conn: &Connection, element: Self::Element<'_>) -> Result<()> {
         let sql = format!("INSERT INTO {} {}", Self::TABLE, Self::INSERT);
@@ -141,11 +192,103 @@ pub trait Repo {
         Self::bind_insert(&mut stmt, element)?;
         match stmt.raw_execute()? {
             1 => Ok(()),
-            c => Err(duckdb::Error::StatementChangedRows(c)),
+            c => Err(backend::Error::StatementChangedRows(c)),
         }
     }
 
+    /// Deletes the row with the given key, returns whether it existed.
+    #[inline]
+    fn delete(conn: &Connection, key: Self::Key) -> Result<bool> {
+        let query = Self::by_key();
+        let sql = format!("DELETE FROM {} WHERE {}", Self::TABLE, query.r#where());
+        let mut stmt = conn.prepare_cached(&sql)?;
+        query.bind_params(&mut stmt, key)?;
+        Ok(stmt.raw_execute()? > 0)
+    }
+
+    /// Inserts the element, replacing the row with the same key.
+    ///
+    /// duckdb's attached sqlite tables don't expose their constraints,
+    /// so this is a delete followed by an insert and should run inside a [`transaction`].
+    #[inline]
+    fn upsert(conn: &Connection, element: Self::Element<'_>) -> Result<()> {
+        Self::delete(conn, Self::key(&element))?;
+        Self::insert(conn, element)
+    }
+
+    /// Upserts all elements inside one transaction, returns the number of elements written.
+    #[inline]
+    fn upsert_all<'e>(
+        conn: &Connection,
+        elements: impl IntoIterator<Item = Self::Element<'e>>,
+    ) -> Result<usize> {
+        transaction(conn, || {
+            let mut count = 0;
+            for element in elements {
+                Self::upsert(conn, element)?;
+                count += 1;
+            }
+            Ok(count)
+        })
+    }
+
     fn bind_insert(stmt: &mut Statement, element: Self::Element<'_>) -> Result<()>;
+}
 
-    fn from_row<'r>(row: &'r Row) -> Result<Self::Element<'r>>;
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::beacon::BeaconBeam;
+
+    fn beacon(x: i32, color: (u8, u8, u8)) -> BeaconBeam {
+        let mut beacon = BeaconBeam::default();
+        beacon.x = x;
+        beacon.y = -20;
+        beacon.color = color;
+        beacon
+    }
+
+    #[test]
+    fn insert_upsert_delete() {
+        let conn = crate::database::open_in_memory();
+        let red = beacon(1, (255, 0, 0));
+        BeaconBeam::insert(&conn, red).unwrap();
+        assert_eq!(BeaconBeam::get_all(&conn).unwrap(), [red]);
+        // The key is taken
+        assert!(BeaconBeam::insert(&conn, red).is_err());
+
+        let blue = beacon(1, (0, 0, 255));
+        BeaconBeam::upsert(&conn, blue).unwrap();
+        assert_eq!(BeaconBeam::get_all(&conn).unwrap(), [blue]);
+
+        let key = BeaconBeam::key(&blue);
+        assert!(BeaconBeam::delete(&conn, key).unwrap());
+        assert!(!BeaconBeam::delete(&conn, key).unwrap());
+        assert!(BeaconBeam::get_all(&conn).unwrap().is_empty());
+
+        let beacons = [beacon(-2, (0, 0, 0)), red, beacon(-2, (1, 1, 1))];
+        assert_eq!(BeaconBeam::upsert_all(&conn, beacons).unwrap(), 3);
+        // The last beacon at -2 replaced the first
+        assert_eq!(BeaconBeam::get_all(&conn).unwrap(), [beacons[2], red]);
+    }
+
+    #[test]
+    fn transactions() {
+        let conn = crate::database::open_in_memory();
+        let red = beacon(1, (255, 0, 0));
+
+        let failed = transaction(&conn, || {
+            BeaconBeam::insert(&conn, red)?;
+            Err::<(), _>(backend::Error::QueryReturnedNoRows)
+        });
+        assert!(matches!(failed, Err(backend::Error::QueryReturnedNoRows)));
+        assert!(BeaconBeam::get_all(&conn).unwrap().is_empty());
+
+        let value = transaction(&conn, || {
+            BeaconBeam::insert(&conn, red)?;
+            Ok::<_, backend::Error>(7)
+        });
+        assert_eq!(value.unwrap(), 7);
+        assert_eq!(BeaconBeam::get_all(&conn).unwrap(), [red]);
+    }
 }
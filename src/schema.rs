@@ -0,0 +1,190 @@
+//! Which DH version wrote a database, and whether this crate can read it.
+//!
+//! DH records every migration script it applied in the `Schema` table,
+//! the number each script name starts with is used as the schema version.
+//! The tables and columns this crate needs are checked directly,
+//! so databases from mod versions we don't know about are accepted as long as they fit.
+
+use core::ops::RangeInclusive;
+
+use crate::{
+    Error, Result,
+    backend::{self, Connection, Row},
+    repo::{All, Query, Repo},
+};
+
+/// Data format versions of `FullData` sections the decoder understands, DH writes
+/// `FullDataSourceV2.DATA_FORMAT_VERSION` into the `DataFormatVersion` column.
+///
+/// The version covers the data points and the mapping, including the
+/// `biome_DH-BSW_block_STATE_{key:value}` string of each entry, sections of other versions
+/// are rejected before any of them is parsed.
+///
+/// <https://gitlab.com/distant-horizons-team/distant-horizons-core/-/blob/main/core/src/main/java/com/seibel/distanthorizons/core/dataObjects/fullData/sources/FullDataSourceV2.java>
+pub const SUPPORTED_FORMAT_VERSIONS: RangeInclusive<u8> = 1..=1;
+
+/// `FullData` columns needed to read sections.
+const REQUIRED_COLUMNS: &[&str] = &[
+    "DetailLevel",
+    "PosX",
+    "PosZ",
+    "MinY",
+    "DataChecksum",
+    "Data",
+    "ColumnGenerationStep",
+    "ColumnWorldCompressionMode",
+    "Mapping",
+    "DataFormatVersion",
+    "CompressionMode",
+    "LastModifiedUnixDateTime",
+    "CreatedUnixDateTime",
+];
+
+/// `FullData` columns added by later migrations, reading works without them but writing doesn't.
+const WRITE_COLUMNS: &[&str] = &["ApplyToParent", "ApplyToChildren"];
+
+/// Whether sections of `version` can be decoded.
+#[inline]
+#[must_use]
+pub fn is_supported_format_version(version: u8) -> bool {
+    SUPPORTED_FORMAT_VERSIONS.contains(&version)
+}
+
+/// A migration script DH applied to the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    pub script: String,
+}
+
+impl Migration {
+    /// The number the script name starts with, 40 for `0040-<description>.sql`.
+    #[inline]
+    #[must_use]
+    pub fn number(&self) -> Option<u32> {
+        let end = self
+            .script
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.script.len());
+        self.script[..end].parse().ok()
+    }
+}
+
+impl Repo for Migration {
+    const SELECTION: &'static str = "ScriptName";
+    const TABLE: &'static str = "Schema";
+
+    type Element<'r> = Migration;
+
+    #[inline]
+    fn from_row<'r>(row: &'r Row) -> backend::Result<Self::Element<'r>> {
+        Ok(Migration {
+            script: row.get("ScriptName")?,
+        })
+    }
+}
+
+/// Layout of a DH database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schema {
+    /// Applied migrations, empty if the database has no `Schema` table
+    pub migrations: Vec<Migration>,
+    /// Columns of the `FullData` table, empty if it doesn't exist
+    pub full_data_columns: Vec<String>,
+    /// Whether the `BeaconBeam` table exists
+    pub beacons: bool,
+}
+
+impl Schema {
+    #[inline]
+    pub fn detect(conn: &Connection) -> Result<Self> {
+        let migrations = if backend::table_columns(conn, Migration::TABLE)?.is_empty() {
+            Vec::new()
+        } else {
+            Migration::select_vec(conn, &All.ordered("ScriptName"), |m| m)?
+        };
+
+        Ok(Self {
+            migrations,
+            full_data_columns: backend::table_columns(conn, "FullData")?,
+            beacons: !backend::table_columns(conn, "BeaconBeam")?.is_empty(),
+        })
+    }
+
+    /// Highest applied migration, `None` without a `Schema` table.
+    #[inline]
+    #[must_use]
+    pub fn version(&self) -> Option<u32> {
+        self.migrations.iter().filter_map(Migration::number).max()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn has_column(&self, column: &str) -> bool {
+        self.full_data_columns
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(column))
+    }
+
+    /// Checks that sections can be read.
+    #[inline]
+    pub fn check_readable(&self) -> Result<()> {
+        self.check_columns(REQUIRED_COLUMNS)
+    }
+
+    /// Checks that sections can be read and written.
+    #[inline]
+    pub fn check_writable(&self) -> Result<()> {
+        self.check_readable()?;
+        self.check_columns(WRITE_COLUMNS)
+    }
+
+    fn check_columns(&self, columns: &[&str]) -> Result<()> {
+        let missing: Vec<_> = columns
+            .iter()
+            .filter(|c| !self.has_column(c))
+            .map(|c| format!("FullData.{c}"))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::UnsupportedSchema {
+            version: self.version(),
+            missing: if self.full_data_columns.is_empty() {
+                "FullData".into()
+            } else {
+                missing.join(", ")
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migration(script: &str) -> Migration {
+        Migration {
+            script: script.to_string(),
+        }
+    }
+
+    #[test]
+    fn migration_number() {
+        assert_eq!(
+            migration("0040-sqlite-removeRenderCache.sql").number(),
+            Some(40)
+        );
+        assert_eq!(migration("0001-initial.sql").number(), Some(1));
+        assert_eq!(migration("12").number(), Some(12));
+        assert_eq!(migration("initial.sql").number(), None);
+        assert_eq!(migration("").number(), None);
+    }
+
+    #[test]
+    fn format_versions() {
+        assert!(is_supported_format_version(1));
+        assert!(!is_supported_format_version(0));
+        assert!(!is_supported_format_version(2));
+    }
+}
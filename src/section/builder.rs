@@ -0,0 +1,373 @@
+//! Creating sections from scratch, for generators, importers and fixtures.
+
+use core::ops::Range;
+
+use crate::{Compression, Error, LightLevel, Result, compression::Compressed};
+
+use super::{
+    Section,
+    columns::Columns,
+    compression::WorldCompression,
+    data::DataPoint,
+    mapping::{Entry, MappingBuilder},
+    pos::Pos,
+    world_gen_step::WorldGenStep,
+};
+
+const WIDTH: usize = Section::WIDTH;
+const LEN: usize = WIDTH * WIDTH;
+
+/// Blocks of one kind stacked in a column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub entry: Entry,
+    /// World heights, the end excluded
+    pub y: Range<i32>,
+    pub sky_light: LightLevel,
+    pub block_light: LightLevel,
+}
+
+impl Run {
+    /// `entry` between `y.start` and `y.end`, fully sky lit.
+    #[inline]
+    #[must_use]
+    pub const fn new(entry: Entry, y: Range<i32>) -> Self {
+        Self {
+            entry,
+            y,
+            sky_light: LightLevel::SUN,
+            block_light: LightLevel::Level0,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_light(mut self, sky_light: LightLevel, block_light: LightLevel) -> Self {
+        self.sky_light = sky_light;
+        self.block_light = block_light;
+        self
+    }
+}
+
+/// Collects the runs of every column and builds a decompressed [`Section`] with its mapping.
+///
+/// ```
+/// use distant_horizons::section::{builder::{Run, SectionBuilder}, mapping::Entry, pos::Pos};
+/// # fn main() -> distant_horizons::Result<()> {
+/// let stone = Entry::new("minecraft:plains", "minecraft:stone");
+/// let mut builder = SectionBuilder::new(Pos::from_block(distant_horizons::DetailLevel::Chunk4, 0, 0));
+/// for dx in 0..64 {
+///     for dz in 0..64 {
+///         builder.push(dx, dz, Run::new(stone.clone(), -64..62))?;
+///     }
+/// }
+/// let mut section = builder.build()?;
+/// assert_eq!(section.mapping().map(|m| m.len()), Some(1));
+/// section.compress()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SectionBuilder {
+    pos: Pos,
+    min_y: i32,
+    compression: Compression,
+    world_compression: WorldCompression,
+    last_modified: i64,
+    created: i64,
+    /// Indexed like [`Columns`]
+    runs: Vec<Vec<Run>>,
+    steps: Vec<WorldGenStep>,
+}
+
+impl SectionBuilder {
+    /// Lowest block of the overworld, the default `min_y`.
+    pub const OVERWORLD_MIN_Y: i32 = -64;
+
+    #[inline]
+    #[must_use]
+    pub fn new(pos: Pos) -> Self {
+        Self {
+            pos,
+            min_y: Self::OVERWORLD_MIN_Y,
+            compression: Compression::Lzma2,
+            world_compression: WorldCompression::MergeSameBlock,
+            last_modified: 0,
+            created: 0,
+            runs: vec![Vec::new(); LEN],
+            steps: vec![WorldGenStep::Empty; LEN],
+        }
+    }
+
+    /// Lowest block of the dimension, runs can't go below it.
+    #[inline]
+    #[must_use]
+    pub const fn with_min_y(mut self, min_y: i32) -> Self {
+        self.min_y = min_y;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Stored for every column.
+    #[inline]
+    #[must_use]
+    pub const fn with_world_compression(mut self, world_compression: WorldCompression) -> Self {
+        self.world_compression = world_compression;
+        self
+    }
+
+    /// Unix timestamps in milliseconds, like DH writes them.
+    #[inline]
+    #[must_use]
+    pub const fn with_timestamps(mut self, created: i64, last_modified: i64) -> Self {
+        self.created = created;
+        self.last_modified = last_modified;
+        self
+    }
+
+    #[inline]
+    fn index(dx: usize, dz: usize) -> Result<usize> {
+        if dx >= WIDTH || dz >= WIDTH {
+            return Err(Error::format(format!(
+                "column {dx},{dz} outside the section"
+            )));
+        }
+        Ok(dx * WIDTH + dz)
+    }
+
+    /// Adds a run to column `(dx, dz)`, in any order.
+    /// An empty column becomes [`WorldGenStep::Light`], fully generated.
+    #[inline]
+    pub fn push(&mut self, dx: usize, dz: usize, run: Run) -> Result<&mut Self> {
+        let i = Self::index(dx, dz)?;
+        if run.y.is_empty() {
+            return Err(Error::format(format!(
+                "empty run {}..{} in column {dx},{dz}",
+                run.y.start, run.y.end
+            )));
+        }
+        self.runs[i].push(run);
+        if self.steps[i] == WorldGenStep::Empty {
+            self.steps[i] = WorldGenStep::Light;
+        }
+        Ok(self)
+    }
+
+    #[inline]
+    pub fn set_world_gen_step(
+        &mut self,
+        dx: usize,
+        dz: usize,
+        step: WorldGenStep,
+    ) -> Result<&mut Self> {
+        self.steps[Self::index(dx, dz)?] = step;
+        Ok(self)
+    }
+
+    /// Checks the runs and packs them into data points, top down like DH stores them.
+    /// Adjacent runs of the same entry and light are merged.
+    ///
+    /// The section is flagged `ApplyToParent` like new data from DH,
+    /// call [`Section::compress`] before writing it.
+    pub fn build(self) -> Result<Section<'static>> {
+        let mut mapping = MappingBuilder::new();
+        let mut data = Vec::with_capacity(LEN);
+
+        for (i, mut runs) in self.runs.into_iter().enumerate() {
+            let (dx, dz) = (i / WIDTH, i % WIDTH);
+            runs.sort_by_key(|run| core::cmp::Reverse(run.y.start));
+
+            let mut points: Vec<DataPoint> = Vec::with_capacity(runs.len());
+            let mut below: Option<&Run> = None;
+            for run in runs.iter().rev() {
+                if let Some(below) = below
+                    && below.y.end > run.y.start
+                {
+                    return Err(Error::format(format!(
+                        "runs {}..{} and {}..{} overlap in column {dx},{dz}",
+                        below.y.start, below.y.end, run.y.start, run.y.end
+                    )));
+                }
+                below = Some(run);
+            }
+
+            for run in &runs {
+                let min_y = run.y.start - self.min_y;
+                let out_of_range = || {
+                    Error::format(format!(
+                        "run {}..{} in column {dx},{dz} out of range above min y {}",
+                        run.y.start, run.y.end, self.min_y
+                    ))
+                };
+                let min_y = u16::try_from(min_y)
+                    .ok()
+                    .filter(|&y| y <= DataPoint::MAX_MIN_Y)
+                    .ok_or_else(out_of_range)?;
+                let height = u16::try_from(run.y.end - run.y.start)
+                    .ok()
+                    .filter(|&h| h <= DataPoint::MAX_HEIGHT)
+                    .ok_or_else(out_of_range)?;
+                let id = mapping.id(&run.entry);
+
+                // The previous point is right above this one
+                if let Some(above) = points.last_mut()
+                    && above.id() == id
+                    && above.min_y() == min_y + height
+                    && above.sky_light() == run.sky_light
+                    && above.block_light() == run.block_light
+                    && above.height() + height <= DataPoint::MAX_HEIGHT
+                {
+                    *above = above
+                        .with_height(above.height() + height)?
+                        .with_min_y(min_y)?;
+                    continue;
+                }
+                points.push(DataPoint::new(
+                    id,
+                    height,
+                    min_y,
+                    run.sky_light as u8,
+                    run.block_light as u8,
+                )?);
+            }
+            data.push(points.into_boxed_slice());
+        }
+
+        let columns = |len| Error::format(format!("{len} columns off"));
+        let compression = self.compression;
+
+        Ok(Section {
+            pos: self.pos,
+            min_y: self.min_y,
+            checksum: 0,
+            data: Compressed::Decompressed {
+                compressor: compression,
+                val: Box::new(Columns::try_from_iter(data).map_err(columns)?),
+            },
+            world_gen_step: Compressed::Decompressed {
+                compressor: compression,
+                val: Columns::try_from_iter(self.steps).map_err(columns)?,
+            },
+            world_compression: Compressed::Decompressed {
+                compressor: compression,
+                val: Columns::new([self.world_compression; LEN]),
+            },
+            mapping: Compressed::Decompressed {
+                compressor: compression,
+                val: mapping.build(),
+            },
+            format_version: *crate::schema::SUPPORTED_FORMAT_VERSIONS.end(),
+            compression,
+            apply_to_parent: Some(true),
+            apply_to_children: Some(false),
+            last_modified: self.last_modified,
+            created: self.created,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DetailLevel;
+
+    fn builder() -> SectionBuilder {
+        SectionBuilder::new(Pos::new(DetailLevel::Chunk4, -1, 2))
+    }
+
+    fn entry(block: &str) -> Entry {
+        Entry::new("minecraft:plains", block)
+    }
+
+    #[test]
+    fn overlapping_runs() {
+        let mut b = builder();
+        b.push(3, 4, Run::new(entry("minecraft:stone"), -64..10))
+            .unwrap();
+        b.push(3, 4, Run::new(entry("minecraft:dirt"), 9..12))
+            .unwrap();
+        assert!(matches!(b.build(), Err(Error::Format(_))));
+
+        // Touching runs don't overlap
+        let mut b = builder();
+        b.push(3, 4, Run::new(entry("minecraft:stone"), -64..10))
+            .unwrap();
+        b.push(3, 4, Run::new(entry("minecraft:dirt"), 10..12))
+            .unwrap();
+        assert!(b.build().is_ok());
+    }
+
+    #[test]
+    fn merges_adjacent_runs() {
+        let stone = entry("minecraft:stone");
+        let mut b = builder();
+        // Out of order, the middle one only touches the others
+        b.push(5, 1, Run::new(stone.clone(), 0..20)).unwrap();
+        b.push(5, 1, Run::new(stone.clone(), -64..0)).unwrap();
+        b.push(5, 1, Run::new(stone.clone(), 20..30)).unwrap();
+        // Separated by a gap
+        b.push(5, 1, Run::new(stone.clone(), 40..41)).unwrap();
+        // Different light
+        b.push(
+            5,
+            1,
+            Run::new(stone, 41..42).with_light(LightLevel::Level0, LightLevel::Level0),
+        )
+        .unwrap();
+        b.push(5, 1, Run::new(entry("minecraft:dirt"), 30..31))
+            .unwrap();
+        let section = b.build().unwrap();
+
+        let points = section.column_data().unwrap().get(5, 1);
+        let spans: Vec<_> = points
+            .iter()
+            .map(|p| (p.min_y(), p.height(), p.id()))
+            .collect();
+        // Top down, relative to min y -64, dirt was added after stone
+        assert_eq!(spans, [(105, 1, 0), (104, 1, 0), (94, 1, 1), (0, 94, 0)]);
+        assert_eq!(points[0].sky_light(), LightLevel::Level0);
+        assert_eq!(points[1].sky_light(), LightLevel::SUN);
+
+        assert_eq!(
+            *section.world_gen_step().unwrap().get(5, 1),
+            WorldGenStep::Light
+        );
+        assert_eq!(
+            *section.world_gen_step().unwrap().get(1, 5),
+            WorldGenStep::Empty
+        );
+        assert!(section.column_data().unwrap().get(1, 5).is_empty());
+    }
+
+    #[test]
+    fn out_of_range() {
+        let stone = entry("minecraft:stone");
+        let build = |min_y: i32, run: Run| {
+            let mut b = builder().with_min_y(min_y);
+            b.push(0, 0, run).unwrap();
+            b.build()
+        };
+
+        // Below min y
+        assert!(build(-64, Run::new(stone.clone(), -65..0)).is_err());
+        // Starts above the highest min y of a data point
+        let max = i32::from(DataPoint::MAX_MIN_Y);
+        assert!(build(0, Run::new(stone.clone(), max..max + 1)).is_ok());
+        assert!(build(0, Run::new(stone.clone(), max + 1..max + 2)).is_err());
+        // Taller than a data point
+        let height = i32::from(DataPoint::MAX_HEIGHT);
+        assert!(build(0, Run::new(stone.clone(), 0..height)).is_ok());
+        assert!(build(0, Run::new(stone.clone(), 0..height + 1)).is_err());
+
+        let mut b = builder();
+        assert!(b.push(64, 0, Run::new(stone.clone(), 0..1)).is_err());
+        assert!(b.push(0, 64, Run::new(stone.clone(), 0..1)).is_err());
+        assert!(b.push(0, 0, Run::new(stone, 1..1)).is_err());
+    }
+}
@@ -1,11 +1,69 @@
-use std::{mem::MaybeUninit, ops::Index};
+use std::{
+    mem::MaybeUninit,
+    ops::{Index, IndexMut},
+};
+
+use super::pos::Pos;
 
 const WIDTH: usize = super::Section::WIDTH;
 const LEN: usize = WIDTH * WIDTH;
 
+/// One value per column of a section, stored x major like DH does.
 #[derive(Debug)]
 pub struct Columns<C>([C; LEN]);
 
+/// Block coordinates of the north west corner of column `(dx, dz)` of the section at `pos`,
+/// `None` below [`Pos::SECTION_MINIMUM_DETAIL_LEVEL`] where columns are narrower than a block.
+#[inline]
+#[must_use]
+pub const fn to_world(pos: Pos, dx: usize, dz: usize) -> Option<(i32, i32)> {
+    let Some(width) = column_width(pos) else {
+        return None;
+    };
+    Some((
+        pos.min_x() + dx as i32 * width,
+        pos.min_z() + dz as i32 * width,
+    ))
+}
+
+/// The column of the section at `pos` containing block `(x, z)`,
+/// `None` outside the section or below [`Pos::SECTION_MINIMUM_DETAIL_LEVEL`].
+#[inline]
+#[must_use]
+pub const fn from_world(pos: Pos, x: i32, z: i32) -> Option<(usize, usize)> {
+    let Some(width) = column_width(pos) else {
+        return None;
+    };
+    if !pos.contains_block(x, z) {
+        return None;
+    }
+    Some((
+        ((x - pos.min_x()) / width) as usize,
+        ((z - pos.min_z()) / width) as usize,
+    ))
+}
+
+/// Width of a column in blocks, `None` below [`Pos::SECTION_MINIMUM_DETAIL_LEVEL`].
+#[inline]
+#[must_use]
+pub const fn column_width(pos: Pos) -> Option<i32> {
+    if (pos.detail_level as u8) < Pos::SECTION_MINIMUM_DETAIL_LEVEL as u8 {
+        return None;
+    }
+    Some(pos.detail_level.block_width() / WIDTH as i32)
+}
+
+#[inline]
+const fn index(dx: usize, dz: usize) -> usize {
+    debug_assert!(dx < WIDTH && dz < WIDTH);
+    dx * WIDTH + dz
+}
+
+#[inline]
+const fn coordinates(index: usize) -> (usize, usize) {
+    (index / WIDTH, index % WIDTH)
+}
+
 impl<C> Columns<C> {
     pub const LEN: usize = WIDTH * WIDTH;
 
@@ -63,13 +121,156 @@ impl<C> Columns<C> {
     }
 }
 
+impl<C> Columns<C> {
+    #[inline]
+    #[must_use]
+    pub fn get(&self, dx: usize, dz: usize) -> &C {
+        &self.0[index(dx, dz)]
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get_mut(&mut self, dx: usize, dz: usize) -> &mut C {
+        &mut self.0[index(dx, dz)]
+    }
+
+    /// `(dx, dz, column)` in storage order.
+    #[inline]
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (usize, usize, &C)> {
+        self.0.iter().enumerate().map(|(i, c)| {
+            let (dx, dz) = coordinates(i);
+            (dx, dz, c)
+        })
+    }
+
+    /// `(dx, dz, column)` in storage order.
+    #[inline]
+    pub fn iter_mut(&mut self) -> impl ExactSizeIterator<Item = (usize, usize, &mut C)> {
+        self.0.iter_mut().enumerate().map(|(i, c)| {
+            let (dx, dz) = coordinates(i);
+            (dx, dz, c)
+        })
+    }
+
+    /// Calls `f` with `(dx, dz, column)` for every column, in storage order.
+    #[inline]
+    #[must_use]
+    pub fn map<D>(&self, mut f: impl FnMut(usize, usize, &C) -> D) -> Columns<D> {
+        Columns(std::array::from_fn(|i| {
+            let (dx, dz) = coordinates(i);
+            f(dx, dz, &self.0[i])
+        }))
+    }
+
+    /// `(dx, dz, column, other column)` in storage order.
+    #[inline]
+    pub fn zip<'a, D>(
+        &'a self,
+        other: &'a Columns<D>,
+    ) -> impl ExactSizeIterator<Item = (usize, usize, &'a C, &'a D)> {
+        self.iter()
+            .zip(other.0.iter())
+            .map(|((dx, dz, c), d)| (dx, dz, c, d))
+    }
+}
+
+impl<C> AsRef<[C]> for Columns<C> {
+    #[inline]
+    fn as_ref(&self) -> &[C] {
+        &self.0
+    }
+}
+
+/// Indexed by `(dz, dx)`, [`Columns::get`] takes them the other way around.
 impl<C> Index<(usize, usize)> for Columns<C> {
     type Output = C;
 
     #[inline]
-    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
-        debug_assert!(x < WIDTH && y < WIDTH);
-        debug_assert!(y * WIDTH + x < LEN);
-        &self.0[y * WIDTH + x]
+    fn index(&self, (dz, dx): (usize, usize)) -> &Self::Output {
+        &self.0[index(dx, dz)]
+    }
+}
+
+/// Indexed by `(dz, dx)` like [`Index`].
+impl<C> IndexMut<(usize, usize)> for Columns<C> {
+    #[inline]
+    fn index_mut(&mut self, (dz, dx): (usize, usize)) -> &mut Self::Output {
+        &mut self.0[index(dx, dz)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DetailLevel;
+
+    #[test]
+    fn world_coordinates() {
+        let pos = Pos::new(DetailLevel::Chunk8, -1, 2);
+        assert_eq!(column_width(pos), Some(2));
+        assert_eq!(to_world(pos, 0, 0), Some((-128, 256)));
+        assert_eq!(to_world(pos, 63, 1), Some((-2, 258)));
+        assert_eq!(from_world(pos, -1, 259), Some((63, 1)));
+        assert_eq!(from_world(pos, -128, 256), Some((0, 0)));
+        assert_eq!(from_world(pos, 0, 256), None);
+
+        // Columns narrower than a block
+        let pos = Pos::new(DetailLevel::Chunk2, 0, 0);
+        assert_eq!(column_width(pos), None);
+        assert_eq!(to_world(pos, 1, 1), None);
+        assert_eq!(from_world(pos, 1, 1), None);
+    }
+
+    /// Every column holding its own index in storage order.
+    fn numbered() -> Columns<usize> {
+        Columns::try_from_iter(0..LEN).unwrap()
+    }
+
+    #[test]
+    fn indexing() {
+        let mut columns = numbered();
+        assert_eq!(*columns.get(1, 2), WIDTH + 2);
+        assert_eq!(columns[(2, 1)], WIDTH + 2);
+
+        columns[(2, 1)] = 7;
+        assert_eq!(*columns.get(1, 2), 7);
+        *columns.get_mut(63, 0) = 8;
+        assert_eq!(columns[(0, 63)], 8);
+        assert_eq!(columns.as_ref()[63 * WIDTH], 8);
+    }
+
+    #[test]
+    fn storage_order() {
+        let mut columns = numbered();
+        let expected = |i| (i / WIDTH, i % WIDTH);
+
+        assert_eq!(columns.iter().len(), LEN);
+        for (i, (dx, dz, &c)) in columns.iter().enumerate() {
+            assert_eq!((dx, dz), expected(i));
+            assert_eq!(c, i);
+        }
+        assert_eq!(
+            columns.iter().nth(1).map(|(dx, dz, _)| (dx, dz)),
+            Some((0, 1))
+        );
+
+        for (i, (dx, dz, c)) in columns.iter_mut().enumerate() {
+            assert_eq!((dx, dz), expected(i));
+            *c = dx * 1000 + dz;
+        }
+        assert_eq!(*columns.get(3, 5), 3005);
+
+        let mapped = columns.map(|dx, dz, &c| (dx, dz, c));
+        for (i, (dx, dz, &(mx, mz, c))) in mapped.iter().enumerate() {
+            assert_eq!((dx, dz), expected(i));
+            assert_eq!((mx, mz, c), (dx, dz, dx * 1000 + dz));
+        }
+
+        let other = numbered();
+        assert_eq!(columns.zip(&other).len(), LEN);
+        for (i, (dx, dz, &c, &o)) in columns.zip(&other).enumerate() {
+            assert_eq!((dx, dz), expected(i));
+            assert_eq!((c, o), (dx * 1000 + dz, i));
+        }
     }
 }
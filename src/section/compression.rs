@@ -7,33 +7,44 @@ pub enum WorldCompression {
 }
 
 impl TryFrom<u8> for WorldCompression {
-    type Error = anyhow::Error;
+    type Error = crate::Error;
 
     #[inline]
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
             0 => Ok(Self::MergeSameBlock),
             1 => Ok(Self::VisuallyEqual),
-            _ => anyhow::bail!("invalid value: {value}"),
+            _ => Err(crate::Error::format(format!(
+                "invalid world compression mode: {value}"
+            ))),
         }
     }
 }
 
 impl TryFrom<Box<[u8]>> for super::columns::Columns<WorldCompression> {
-    type Error = anyhow::Error;
+    type Error = crate::Error;
 
     #[inline]
     fn try_from(data: Box<[u8]>) -> Result<Self, Self::Error> {
-        use anyhow::Context;
-
         let mut data_iter = data.into_iter();
         let mut cols = [WorldCompression::MergeSameBlock; Self::LEN];
         cols.iter_mut().try_for_each(|col| {
-            let next = data_iter.next().context("not enough values")?;
+            let next = data_iter
+                .next()
+                .ok_or_else(|| crate::Error::format("not enough values"))?;
             *col = next.try_into()?;
-            anyhow::Ok(())
+            Ok::<_, crate::Error>(())
         })?;
-        // anyhow::ensure!(data_iter.next().is_none(), "too many values");
+        // ensure!(data_iter.next().is_none(), "too many values");
         Ok(Self::new(cols))
     }
 }
+
+impl TryFrom<&super::columns::Columns<WorldCompression>> for Box<[u8]> {
+    type Error = crate::Error;
+
+    #[inline]
+    fn try_from(cols: &super::columns::Columns<WorldCompression>) -> Result<Self, Self::Error> {
+        Ok(cols.as_ref().iter().map(|&col| col as u8).collect())
+    }
+}
@@ -1,12 +1,11 @@
 // https://gitlab.com/distant-horizons-team/distant-horizons-core/-/blob/main/core/src/main/java/com/seibel/distanthorizons/core/dataObjects/fullData/FullDataPointIdMap.java
 // https://gitlab.com/distant-horizons-team/distant-horizons-core/-/blob/main/core/src/main/java/com/seibel/distanthorizons/core/util/FullDataPointUtil.java
-use anyhow::Context;
 
 use crate::light::LightLevel;
 
 use super::columns::Columns;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct DataPoint {
     id: u32,
     meta: u32,
@@ -32,6 +31,99 @@ impl DataPoint {
     const SKY_LIGHT_MASK: u32 = (1 << Self::SKY_LIGHT_WIDTH) - 1;
     const BLOCK_LIGHT_MASK: u32 = (1 << Self::BLOCK_LIGHT_WIDTH) - 1;
 
+    /// Highest `height` the packed field can hold.
+    pub const MAX_HEIGHT: u16 = Self::HEIGHT_MASK as u16;
+    /// Highest `min_y` the packed field can hold.
+    pub const MAX_MIN_Y: u16 = Self::MIN_Y_MASK as u16;
+
+    /// A data point with mapping `id`, `height` blocks high starting `min_y` above the
+    /// section's `min_y`. Fails if a value doesn't fit in its field.
+    #[inline]
+    pub fn new(
+        id: u32,
+        height: u16,
+        min_y: u16,
+        sky_light: u8,
+        block_light: u8,
+    ) -> crate::Result<Self> {
+        Ok(Self { id, meta: 0 }
+            .with_height(height)?
+            .with_min_y(min_y)?
+            .with_sky_light(LightLevel::try_from(sky_light)?)
+            .with_block_light(LightLevel::try_from(block_light)?))
+    }
+
+    /// Replaces the bits of one field.
+    #[inline]
+    const fn with_field(self, value: u32, offset: usize, mask: u32) -> Self {
+        Self {
+            id: self.id,
+            meta: (self.meta & !(mask << offset)) | ((value & mask) << offset),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_id(self, id: u32) -> Self {
+        Self {
+            id,
+            meta: self.meta,
+        }
+    }
+
+    #[inline]
+    pub fn with_height(self, height: u16) -> crate::Result<Self> {
+        if height > Self::MAX_HEIGHT {
+            return Err(crate::Error::format(format!(
+                "height {height} doesn't fit in {} bits",
+                Self::HEIGHT_WIDTH
+            )));
+        }
+        Ok(self.with_field(height as u32, Self::HEIGHT_OFFSET, Self::HEIGHT_MASK))
+    }
+
+    #[inline]
+    pub fn with_min_y(self, min_y: u16) -> crate::Result<Self> {
+        if min_y > Self::MAX_MIN_Y {
+            return Err(crate::Error::format(format!(
+                "min y {min_y} doesn't fit in {} bits",
+                Self::MIN_Y_WIDTH
+            )));
+        }
+        Ok(self.with_field(min_y as u32, Self::MIN_Y_OFFSET, Self::MIN_Y_MASK))
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_sky_light(self, sky_light: LightLevel) -> Self {
+        self.with_field(
+            sky_light as u32,
+            Self::SKY_LIGHT_OFFSET,
+            Self::SKY_LIGHT_MASK,
+        )
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_block_light(self, block_light: LightLevel) -> Self {
+        self.with_field(
+            block_light as u32,
+            Self::BLOCK_LIGHT_OFFSET,
+            Self::BLOCK_LIGHT_MASK,
+        )
+    }
+
+    /// The big endian layout DH stores, the inverse of `From<[u8; 8]>`.
+    #[inline]
+    #[must_use]
+    pub const fn to_bytes(self) -> [u8; 8] {
+        let meta = self.meta.to_be_bytes();
+        let id = self.id.to_be_bytes();
+        [
+            meta[0], meta[1], meta[2], meta[3], id[0], id[1], id[2], id[3],
+        ]
+    }
+
     #[inline]
     #[must_use]
     pub const fn id(&self) -> u32 {
@@ -80,6 +172,13 @@ impl core::fmt::Debug for DataPoint {
     }
 }
 
+impl From<DataPoint> for [u8; 8] {
+    #[inline]
+    fn from(point: DataPoint) -> Self {
+        point.to_bytes()
+    }
+}
+
 impl From<[u8; 8]> for DataPoint {
     #[inline]
     fn from(value: [u8; 8]) -> Self {
@@ -99,7 +198,7 @@ impl From<[u8; 8]> for DataPoint {
 // }
 
 impl TryFrom<&[u8]> for Columns<Box<[DataPoint]>> {
-    type Error = anyhow::Error;
+    type Error = crate::Error;
 
     #[inline]
     fn try_from(mut data: &[u8]) -> Result<Self, Self::Error> {
@@ -108,14 +207,18 @@ impl TryFrom<&[u8]> for Columns<Box<[DataPoint]>> {
         let mut cols = std::array::from_fn(|_| MaybeUninit::uninit());
         for col in &mut cols {
             let mut len = [0, 0];
-            data.read_exact(&mut len).context("reading u16")?;
+            data.read_exact(&mut len)
+                .map_err(|_| crate::Error::format("missing column length"))?;
             let len: usize = u16::from_be_bytes(len).into();
-            anyhow::ensure!(data.len() >= len * 8, "not enough data for column");
+            if data.len() < len * 8 {
+                return Err(crate::Error::format("not enough data for column"));
+            }
 
             let mut points = Vec::with_capacity(len);
             for p in points.spare_capacity_mut() {
                 let mut point = [0; 8];
-                data.read_exact(&mut point).context("reading datapoint")?;
+                data.read_exact(&mut point)
+                    .map_err(|_| crate::Error::format("missing data point"))?;
                 p.write(point.into());
             }
             // Safety: we just wrote len elements
@@ -131,7 +234,7 @@ impl TryFrom<&[u8]> for Columns<Box<[DataPoint]>> {
 }
 
 impl TryFrom<Box<[u8]>> for Columns<Box<[super::data::DataPoint]>> {
-    type Error = anyhow::Error;
+    type Error = crate::Error;
 
     #[inline]
     fn try_from(data: Box<[u8]>) -> Result<Self, Self::Error> {
@@ -140,10 +243,90 @@ impl TryFrom<Box<[u8]>> for Columns<Box<[super::data::DataPoint]>> {
 }
 
 impl TryFrom<Box<[u8]>> for Box<Columns<Box<[super::data::DataPoint]>>> {
-    type Error = anyhow::Error;
+    type Error = crate::Error;
 
     #[inline]
     fn try_from(data: Box<[u8]>) -> Result<Self, Self::Error> {
         Columns::try_from(data.as_ref()).map(Self::new)
     }
 }
+
+impl TryFrom<&Box<Columns<Box<[DataPoint]>>>> for Box<[u8]> {
+    type Error = crate::Error;
+
+    #[inline]
+    fn try_from(cols: &Box<Columns<Box<[DataPoint]>>>) -> Result<Self, Self::Error> {
+        let points: usize = cols.as_ref().as_ref().iter().map(|col| col.len()).sum();
+        let mut data = Vec::with_capacity(Columns::<()>::LEN * 2 + points * 8);
+        for col in cols.as_ref().as_ref() {
+            let len = u16::try_from(col.len())
+                .map_err(|_| crate::Error::format("more than 65535 data points in a column"))?;
+            data.extend_from_slice(&len.to_be_bytes());
+            for &point in col {
+                data.extend_from_slice(&point.to_bytes());
+            }
+        }
+        Ok(data.into_boxed_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields() {
+        let point = DataPoint::new(70_000, 3, DataPoint::MAX_MIN_Y, 15, 7).unwrap();
+        assert_eq!(point.id(), 70_000);
+        assert_eq!(point.height(), 3);
+        assert_eq!(point.min_y(), DataPoint::MAX_MIN_Y);
+        assert_eq!(point.sky_light(), LightLevel::Level15);
+        assert_eq!(point.block_light(), LightLevel::Level7);
+    }
+
+    #[test]
+    fn round_trip() {
+        let points = [
+            DataPoint::new(0, 0, 0, 0, 0).unwrap(),
+            DataPoint::new(1, 1, 2, 3, 4).unwrap(),
+            DataPoint::new(
+                u32::MAX,
+                DataPoint::MAX_HEIGHT,
+                DataPoint::MAX_MIN_Y,
+                15,
+                15,
+            )
+            .unwrap(),
+        ];
+        for point in points {
+            assert_eq!(DataPoint::from(point.to_bytes()), point);
+            assert_eq!(<[u8; 8]>::from(point), point.to_bytes());
+        }
+
+        let bytes = [0x12, 0x34, 0x56, 0x78, 0, 0, 1, 2];
+        assert_eq!(DataPoint::from(bytes).to_bytes(), bytes);
+        assert_eq!(DataPoint::from(bytes).id(), 0x102);
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert!(DataPoint::new(0, DataPoint::MAX_HEIGHT + 1, 0, 0, 0).is_err());
+        assert!(DataPoint::new(0, 0, DataPoint::MAX_MIN_Y + 1, 0, 0).is_err());
+        assert!(DataPoint::new(0, 0, 0, 16, 0).is_err());
+        assert!(DataPoint::new(0, 0, 0, 0, 16).is_err());
+
+        let point = DataPoint::new(5, 10, 20, 15, 0).unwrap();
+        assert!(point.with_height(DataPoint::MAX_HEIGHT + 1).is_err());
+        assert!(point.with_min_y(DataPoint::MAX_MIN_Y + 1).is_err());
+        // Setters only change their own field
+        let changed = point
+            .with_height(DataPoint::MAX_HEIGHT)
+            .unwrap()
+            .with_block_light(LightLevel::Level3)
+            .with_id(6);
+        assert_eq!(
+            changed,
+            DataPoint::new(6, DataPoint::MAX_HEIGHT, 20, 15, 3).unwrap()
+        );
+    }
+}
@@ -0,0 +1,552 @@
+//! Building a parent section out of its four children, and filling children from their parent.
+//!
+//! This is an approximation, not a port of DH's downsampling in `FullDataSourceV2`:
+//! a parent column covers 2x2 columns of one child and keeps one of them as it is,
+//! the one with the highest surface, instead of combining their data points like DH does.
+//! The result is a valid section DH can read and update, but it can differ from the parent
+//! DH would write for the same children. It fills in missing detail levels until DH rebuilds
+//! them, comparing it with the parents DH wrote doesn't tell whether those are stale.
+//!
+//! <https://gitlab.com/distant-horizons-team/distant-horizons-core/-/blob/main/core/src/main/java/com/seibel/distanthorizons/core/dataObjects/fullData/sources/FullDataSourceV2.java>
+
+use crate::{Compression, Error, Result, block::Block, compression::Compressed};
+
+use super::{
+    Section,
+    columns::Columns,
+    compression::WorldCompression,
+    data::DataPoint,
+    mapping::{Entry, Mapping, MappingBuilder},
+    pos::Pos,
+    world_gen_step::WorldGenStep,
+};
+
+const WIDTH: usize = Section::WIDTH;
+const HALF: usize = WIDTH / 2;
+
+/// The decompressed fields of a section.
+#[derive(Clone, Copy)]
+struct Source<'s> {
+    min_y: i32,
+    data: &'s Columns<Box<[DataPoint]>>,
+    steps: &'s Columns<WorldGenStep>,
+    world_compression: &'s Columns<WorldCompression>,
+    mapping: &'s Mapping,
+}
+
+impl<'s> Source<'s> {
+    fn new(section: &'s Section<'_>) -> Result<Self> {
+        let (Some(data), Some(steps), Some(world_compression), Some(mapping)) = (
+            section.column_data(),
+            section.world_gen_step(),
+            section.world_compression(),
+            section.mapping(),
+        ) else {
+            return Err(Error::format(format!(
+                "section {} is not decompressed",
+                section.pos
+            )));
+        };
+
+        Ok(Self {
+            min_y: section.min_y,
+            data,
+            steps,
+            world_compression,
+            mapping,
+        })
+    }
+
+    #[inline]
+    fn column(&self, x: usize, z: usize, min_y: i32) -> Column<'s> {
+        Column {
+            points: self.data.get(x, z),
+            mapping: self.mapping,
+            step: *self.steps.get(x, z),
+            world_compression: *self.world_compression.get(x, z),
+            min_y_offset: self.min_y - min_y,
+        }
+    }
+}
+
+/// One column of a source section, relative to the `min_y` of the section being built.
+#[derive(Clone, Copy)]
+struct Column<'s> {
+    points: &'s [DataPoint],
+    mapping: &'s Mapping,
+    step: WorldGenStep,
+    world_compression: WorldCompression,
+    /// Offset of the source's `min_y` over the built section's
+    min_y_offset: i32,
+}
+
+impl Column<'_> {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.step == WorldGenStep::Empty || self.points.is_empty()
+    }
+
+    /// Whether the column was generated at its own detail level, not derived from another one.
+    #[inline]
+    fn is_generated(&self) -> bool {
+        !self.is_empty() && self.step != WorldGenStep::DownSampled
+    }
+
+    /// Entry of `point`, an error if its id is outside the mapping.
+    #[inline]
+    fn entry(&self, point: &DataPoint) -> Result<&Entry> {
+        self.mapping.get(point.id() as usize).ok_or_else(|| {
+            Error::format(format!("data point id {} outside the mapping", point.id()))
+        })
+    }
+
+    /// Top of the highest non air data point, relative to the built section's `min_y`.
+    #[inline]
+    fn top(&self) -> Result<Option<i32>> {
+        // Data points are stored top down
+        for p in self.points {
+            if !self.entry(p)?.is_air() {
+                return Ok(Some(
+                    self.min_y_offset + i32::from(p.min_y()) + i32::from(p.height()),
+                ));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The column a parent keeps out of the 2x2 it covers:
+/// generated columns over empty ones, then the highest surface, then the first in
+/// `(0, 0), (1, 0), (0, 1), (1, 1)` order.
+fn pick<'s>(columns: [Column<'s>; 4]) -> Result<Column<'s>> {
+    let mut best = columns[0];
+    for column in &columns[1..] {
+        let better = match (best.is_empty(), column.is_empty()) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => column.top()? > best.top()?,
+        };
+        if better {
+            best = *column;
+        }
+    }
+    Ok(best)
+}
+
+/// The fields of the section being built, column by column in storage order, x major.
+struct Output {
+    mapping: MappingBuilder,
+    data: Vec<Box<[DataPoint]>>,
+    steps: Vec<WorldGenStep>,
+    world_compression: Vec<WorldCompression>,
+}
+
+impl Output {
+    fn new() -> Self {
+        Self {
+            mapping: MappingBuilder::new(),
+            data: Vec::with_capacity(WIDTH * WIDTH),
+            steps: Vec::with_capacity(WIDTH * WIDTH),
+            world_compression: Vec::with_capacity(WIDTH * WIDTH),
+        }
+    }
+
+    /// Copies the data points of `column`, moved to the new `min_y` and mapping.
+    fn push(&mut self, column: Column<'_>, step: WorldGenStep) -> Result<()> {
+        let mut points = Vec::with_capacity(column.points.len());
+        for point in column.points {
+            let min_y = i32::from(point.min_y()) + column.min_y_offset;
+            let min_y = u16::try_from(min_y).map_err(|_| {
+                Error::format(format!("data point at relative y {min_y} out of range"))
+            })?;
+            let id = self.mapping.id(column.entry(point)?);
+            points.push(point.with_min_y(min_y)?.with_id(id));
+        }
+
+        self.data.push(points.into_boxed_slice());
+        self.steps.push(step);
+        self.world_compression.push(column.world_compression);
+        Ok(())
+    }
+
+    /// A column without data points.
+    fn push_empty(&mut self) {
+        self.data.push(Box::default());
+        self.steps.push(WorldGenStep::Empty);
+        self.world_compression
+            .push(WorldCompression::MergeSameBlock);
+    }
+
+    fn finish(
+        self,
+        pos: Pos,
+        min_y: i32,
+        compression: Compression,
+        (last_modified, created): (i64, i64),
+    ) -> Result<Section<'static>> {
+        let columns = |len| Error::format(format!("{len} columns off"));
+
+        Ok(Section {
+            pos,
+            min_y,
+            checksum: 0,
+            data: Compressed::Decompressed {
+                compressor: compression,
+                val: Box::new(Columns::try_from_iter(self.data).map_err(columns)?),
+            },
+            world_gen_step: Compressed::Decompressed {
+                compressor: compression,
+                val: Columns::try_from_iter(self.steps).map_err(columns)?,
+            },
+            world_compression: Compressed::Decompressed {
+                compressor: compression,
+                val: Columns::try_from_iter(self.world_compression).map_err(columns)?,
+            },
+            mapping: Compressed::Decompressed {
+                compressor: compression,
+                val: self.mapping.build(),
+            },
+            format_version: *crate::schema::SUPPORTED_FORMAT_VERSIONS.end(),
+            compression,
+            apply_to_parent: Some(false),
+            apply_to_children: Some(false),
+            last_modified,
+            created,
+        })
+    }
+}
+
+impl Section<'_> {
+    /// Merges four decompressed sections into an approximation of their parent,
+    /// see the module documentation for how it differs from DH's.
+    ///
+    /// The children are in [`Pos::children`] order.
+    /// Every column of the parent is [`WorldGenStep::DownSampled`], unless all the columns
+    /// it covers are empty. The parent's `min_y` is the lowest of the children,
+    /// its mapping only contains the entries still used. It is flagged `ApplyToParent`.
+    #[inline]
+    pub fn downsample(children: [&Section<'_>; 4]) -> Result<Section<'static>> {
+        let first = children[0].pos;
+        let parent = first
+            .parent()
+            .ok_or_else(|| Error::format(format!("section {first} has no parent")))?;
+
+        Self::downsample_partial(parent, children.map(Some), None)
+    }
+
+    /// Like [`Self::downsample`] with some children missing.
+    ///
+    /// The quarter of a missing child is copied from `base`, the previous version of the parent,
+    /// or left empty without one.
+    pub fn downsample_partial(
+        pos: Pos,
+        children: [Option<&Section<'_>>; 4],
+        base: Option<&Section<'_>>,
+    ) -> Result<Section<'static>> {
+        let expected = pos
+            .children()
+            .ok_or_else(|| Error::format(format!("section {pos} has no children")))?;
+        for (child, expected) in children.iter().zip(expected) {
+            if let Some(child) = child
+                && child.pos != expected
+            {
+                return Err(Error::format(format!(
+                    "section {} is not child {expected} of {pos}",
+                    child.pos
+                )));
+            }
+        }
+        if let Some(base) = base
+            && base.pos != pos
+        {
+            return Err(Error::format(format!(
+                "section {} can't be the base of {pos}",
+                base.pos
+            )));
+        }
+
+        let present = || children.iter().flatten().copied().chain(base);
+        let min_y = present()
+            .map(|s| s.min_y)
+            .min()
+            .ok_or_else(|| Error::format(format!("no children of {pos} to downsample")))?;
+        let last_modified = present().map(|s| s.last_modified).max().unwrap_or_default();
+        let created = base
+            .map(|b| b.created)
+            .or_else(|| present().map(|s| s.created).min())
+            .unwrap_or_default();
+        let compression = present()
+            .next()
+            .map_or_else(Compression::default, |s| s.compression);
+
+        let mut sources = [None; 4];
+        for (source, child) in sources.iter_mut().zip(children) {
+            *source = child.map(Source::new).transpose()?;
+        }
+        let base = base.map(Source::new).transpose()?;
+
+        let mut output = Output::new();
+        // Storage order
+        for x in 0..WIDTH {
+            for z in 0..WIDTH {
+                match (sources[x / HALF + 2 * (z / HALF)], base) {
+                    (Some(child), _) => {
+                        let (cx, cz) = (2 * (x % HALF), 2 * (z % HALF));
+                        let column = pick(
+                            [(0, 0), (1, 0), (0, 1), (1, 1)]
+                                .map(|(dx, dz)| child.column(cx + dx, cz + dz, min_y)),
+                        )?;
+                        let step = if column.is_empty() {
+                            WorldGenStep::Empty
+                        } else {
+                            WorldGenStep::DownSampled
+                        };
+                        output.push(column, step)?;
+                    }
+                    (None, Some(base)) => {
+                        let column = base.column(x, z, min_y);
+                        output.push(column, column.step)?;
+                    }
+                    (None, None) => output.push_empty(),
+                }
+            }
+        }
+
+        let mut parent = output.finish(pos, min_y, compression, (last_modified, created))?;
+        // DH still has to build this parent its own way and update the ones above it
+        parent.apply_to_parent = Some(true);
+        Ok(parent)
+    }
+
+    /// `child` with the columns that have no generated data of their own replaced
+    /// by the columns of this section covering them, as [`WorldGenStep::DownSampled`].
+    ///
+    /// Downsampled columns of this section are used too, so a child filled this way
+    /// can fill its own children in turn.
+    pub fn upsample(&self, child: &Section<'_>) -> Result<Section<'static>> {
+        let pos = child.pos;
+        if pos.parent() != Some(self.pos) {
+            return Err(Error::format(format!(
+                "section {pos} is not a child of {}",
+                self.pos
+            )));
+        }
+
+        let parent = Source::new(self)?;
+        let source = Source::new(child)?;
+        let min_y = self.min_y.min(child.min_y);
+        // Offset of the child's columns inside this section
+        let ox = (pos.x - 2 * self.pos.x) as usize * HALF;
+        let oz = (pos.z - 2 * self.pos.z) as usize * HALF;
+
+        let mut output = Output::new();
+        // Storage order
+        for x in 0..WIDTH {
+            for z in 0..WIDTH {
+                let own = source.column(x, z, min_y);
+                let covering = parent.column(ox + x / 2, oz + z / 2, min_y);
+                if !own.is_generated() && !covering.is_empty() {
+                    output.push(covering, WorldGenStep::DownSampled)?;
+                } else {
+                    output.push(own, own.step)?;
+                }
+            }
+        }
+
+        output.finish(
+            pos,
+            min_y,
+            child.compression,
+            (child.last_modified.max(self.last_modified), child.created),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DetailLevel,
+        section::{
+            builder::{Run, SectionBuilder},
+            columns,
+        },
+    };
+
+    const PARENT: Pos = Pos::new(DetailLevel::Chunk8, -1, 0);
+
+    fn entry(block: &str) -> Entry {
+        Entry::new("minecraft:plains", block)
+    }
+
+    /// `block` from the bottom to `top` in every column, except `(dx, dz, run)`.
+    fn child(
+        pos: Pos,
+        block: &str,
+        top: i32,
+        except: Option<(usize, usize, Run)>,
+    ) -> Section<'static> {
+        let mut builder = SectionBuilder::new(pos);
+        for dx in 0..WIDTH {
+            for dz in 0..WIDTH {
+                let run = match &except {
+                    Some((x, z, run)) if (*x, *z) == (dx, dz) => run.clone(),
+                    _ => Run::new(entry(block), -64..top),
+                };
+                builder.push(dx, dz, run).unwrap();
+            }
+        }
+        builder.build().unwrap()
+    }
+
+    fn surface(section: &Section<'_>, dx: usize, dz: usize) -> Option<(String, i32)> {
+        let points = section.column_data().unwrap().get(dx, dz);
+        let top = points.first()?;
+        let entry = &section.mapping().unwrap()[top];
+        Some((
+            entry.full_identifier().to_string(),
+            section.min_y + i32::from(top.min_y()) + i32::from(top.height()),
+        ))
+    }
+
+    #[test]
+    fn quadrants() {
+        let blocks = [
+            "minecraft:stone",
+            "minecraft:dirt",
+            "minecraft:sand",
+            "minecraft:gravel",
+        ];
+        let children: Vec<_> = PARENT
+            .children()
+            .unwrap()
+            .into_iter()
+            .zip(blocks)
+            .map(|(pos, block)| child(pos, block, 0, None))
+            .collect();
+        let parent =
+            Section::downsample([&children[0], &children[1], &children[2], &children[3]]).unwrap();
+        assert_eq!(parent.pos, PARENT);
+        assert_eq!(parent.mapping().unwrap().len(), 4);
+        assert!(parent.apply_to_parent());
+
+        // (1, 0) is the child further along x, (0, 1) the one further along z
+        assert_eq!(surface(&parent, 0, 0).unwrap().0, blocks[0]);
+        assert_eq!(surface(&parent, 63, 0).unwrap().0, blocks[1]);
+        assert_eq!(surface(&parent, 0, 63).unwrap().0, blocks[2]);
+        assert_eq!(surface(&parent, 63, 63).unwrap().0, blocks[3]);
+
+        let steps = parent.world_gen_step().unwrap();
+        for (dx, dz, _) in parent.column_data().unwrap().iter() {
+            let (x, z) = columns::to_world(PARENT, dx, dz).unwrap();
+            let i = children
+                .iter()
+                .position(|c| c.pos.contains_block(x, z))
+                .unwrap();
+            assert_eq!(
+                surface(&parent, dx, dz).unwrap().0,
+                blocks[i],
+                "column {dx},{dz}"
+            );
+            assert_eq!(*steps.get(dx, dz), WorldGenStep::DownSampled);
+        }
+    }
+
+    #[test]
+    fn highest_surface() {
+        // Parent column (1, 2) covers the child columns 2..4 along x and 4..6 along z
+        let tower = Run::new(entry("minecraft:gravel"), -64..10);
+        let first = child(
+            PARENT.children().unwrap()[0],
+            "minecraft:stone",
+            0,
+            Some((3, 5, tower)),
+        );
+        let parent =
+            Section::downsample_partial(PARENT, [Some(&first), None, None, None], None).unwrap();
+
+        assert_eq!(
+            surface(&parent, 1, 2),
+            Some(("minecraft:gravel".to_string(), 10))
+        );
+        assert_eq!(
+            surface(&parent, 1, 3),
+            Some(("minecraft:stone".to_string(), 0))
+        );
+        assert_eq!(
+            surface(&parent, 2, 2),
+            Some(("minecraft:stone".to_string(), 0))
+        );
+
+        // Missing children leave their quarter empty
+        assert_eq!(surface(&parent, 40, 10), None);
+        assert_eq!(
+            *parent.world_gen_step().unwrap().get(40, 10),
+            WorldGenStep::Empty
+        );
+    }
+
+    #[test]
+    fn upsample() {
+        // Covers the child (1, 0) with its columns 32.. along x
+        let dirt = Run::new(entry("minecraft:dirt"), -64..5);
+        let mut parent = child(PARENT, "minecraft:stone", 0, Some((33, 1, dirt)));
+        let steps = parent.world_gen_step.as_mut().unwrap();
+        *steps.get_mut(36, 4) = WorldGenStep::Empty;
+        *steps.get_mut(38, 6) = WorldGenStep::DownSampled;
+
+        let pos = PARENT.children().unwrap()[1];
+        let mut builder = SectionBuilder::new(pos);
+        builder
+            .push(0, 0, Run::new(entry("minecraft:gravel"), -64..10))
+            .unwrap()
+            .push(5, 5, Run::new(entry("minecraft:gravel"), -64..10))
+            .unwrap()
+            .set_world_gen_step(5, 5, WorldGenStep::DownSampled)
+            .unwrap();
+        let child = parent.upsample(&builder.build().unwrap()).unwrap();
+        assert_eq!(child.pos, pos);
+        let steps = child.world_gen_step().unwrap();
+
+        // Generated columns are kept
+        assert_eq!(
+            surface(&child, 0, 0),
+            Some(("minecraft:gravel".to_string(), 10))
+        );
+        assert_eq!(*steps.get(0, 0), WorldGenStep::Light);
+        // Every child column covered by a parent column is filled from it
+        for (dx, dz) in [(2, 2), (3, 3), (2, 3)] {
+            assert_eq!(
+                surface(&child, dx, dz),
+                Some(("minecraft:dirt".to_string(), 5))
+            );
+            assert_eq!(*steps.get(dx, dz), WorldGenStep::DownSampled);
+        }
+        // So are downsampled ones, from generated and downsampled parent columns
+        for (dx, dz) in [(5, 5), (12, 13)] {
+            assert_eq!(
+                surface(&child, dx, dz),
+                Some(("minecraft:stone".to_string(), 0))
+            );
+            assert_eq!(*steps.get(dx, dz), WorldGenStep::DownSampled);
+        }
+        // Empty parent columns leave the child empty
+        assert_eq!(surface(&child, 8, 9), None);
+        assert_eq!(*steps.get(8, 9), WorldGenStep::Empty);
+
+        let other = SectionBuilder::new(Pos::new(DetailLevel::Chunk4, 0, 0))
+            .build()
+            .unwrap();
+        assert!(matches!(parent.upsample(&other), Err(Error::Format(_))));
+    }
+
+    #[test]
+    fn id_outside_mapping() {
+        let mut first = child(PARENT.children().unwrap()[0], "minecraft:stone", 0, None);
+        let column = first.data.as_mut().unwrap().get_mut(5, 7);
+        column[0] = column[0].with_id(9);
+
+        let error = Section::downsample_partial(PARENT, [Some(&first), None, None, None], None);
+        assert!(matches!(error, Err(Error::Format(_))));
+    }
+}
@@ -0,0 +1,493 @@
+//! Changing the blocks of a decompressed section in place.
+//!
+//! A column of a coarser detail level stands for several blocks, it is edited as a whole.
+//! Edited columns keep DH's layout: data points top down, the ones right above each other
+//! with the same entry and light merged, and a mapping without duplicate or unused entries.
+
+use core::{cmp::Reverse, ops::Range};
+
+use crate::{Error, LightLevel, Result};
+
+use super::{
+    Section,
+    columns::{self, Columns},
+    data::DataPoint,
+    mapping::{Entry, MappingBuilder},
+    world_gen_step::WorldGenStep,
+};
+
+/// A box of blocks in world coordinates, the ends excluded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Region {
+    pub x: Range<i32>,
+    pub y: Range<i32>,
+    pub z: Range<i32>,
+}
+
+impl Region {
+    #[inline]
+    #[must_use]
+    pub const fn new(x: Range<i32>, y: Range<i32>, z: Range<i32>) -> Self {
+        Self { x, y, z }
+    }
+
+    /// The single block at `(x, y, z)`, `None` if a coordinate is `i32::MAX`
+    /// and the end of its range doesn't fit.
+    #[inline]
+    #[must_use]
+    pub const fn block(x: i32, y: i32, z: i32) -> Option<Self> {
+        let (Some(x_end), Some(y_end), Some(z_end)) =
+            (x.checked_add(1), y.checked_add(1), z.checked_add(1))
+        else {
+            return None;
+        };
+        Some(Self::new(x..x_end, y..y_end, z..z_end))
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty() || self.y.is_empty() || self.z.is_empty()
+    }
+}
+
+/// Replaces the blocks of `y` with a data point of `id`, splitting the ones it overlaps.
+/// The new data point takes the light of the highest one it covers.
+fn paint(points: &mut Vec<DataPoint>, y: Range<u16>, id: u32) -> Result<()> {
+    let mut light = None;
+    let mut painted = Vec::with_capacity(points.len() + 2);
+    for &point in points.iter() {
+        let (bottom, top) = (point.min_y(), point.min_y() + point.height());
+        if top <= y.start || bottom >= y.end {
+            painted.push(point);
+            continue;
+        }
+
+        light.get_or_insert((point.sky_light(), point.block_light()));
+        if top > y.end {
+            painted.push(point.with_min_y(y.end)?.with_height(top - y.end)?);
+        }
+        if bottom < y.start {
+            painted.push(point.with_height(y.start - bottom)?);
+        }
+    }
+
+    let (sky_light, block_light) = light.unwrap_or((LightLevel::SUN, LightLevel::Level0));
+    painted.push(DataPoint::new(
+        id,
+        y.end - y.start,
+        y.start,
+        sky_light as u8,
+        block_light as u8,
+    )?);
+    *points = painted;
+    Ok(())
+}
+
+/// Sorts the data points top down and joins the ones right above each other
+/// with the same entry and light.
+fn merge(points: &mut Vec<DataPoint>) -> Result<()> {
+    points.sort_by_key(|p| Reverse(p.min_y()));
+
+    let mut merged: Vec<DataPoint> = Vec::with_capacity(points.len());
+    for &point in points.iter() {
+        if let Some(above) = merged.last_mut()
+            && above.id() == point.id()
+            && above.min_y() == point.min_y() + point.height()
+            && above.sky_light() == point.sky_light()
+            && above.block_light() == point.block_light()
+            && above.height() + point.height() <= DataPoint::MAX_HEIGHT
+        {
+            *above = above
+                .with_height(above.height() + point.height())?
+                .with_min_y(point.min_y())?;
+            continue;
+        }
+        merged.push(point);
+    }
+    *points = merged;
+    Ok(())
+}
+
+impl Section<'_> {
+    /// Sets the block at world coordinates `(x, y, z)`, in the whole column containing it.
+    #[inline]
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, entry: &Entry) -> Result<()> {
+        if !self.pos.contains_block(x, z) {
+            return Err(Error::format(format!(
+                "block {x},{z} is outside section {}",
+                self.pos
+            )));
+        }
+        let region = Region::block(x, y, z)
+            .ok_or_else(|| Error::format(format!("block {x},{y},{z} is out of range")))?;
+        self.fill(&region, entry).map(drop)
+    }
+
+    /// Sets the blocks of `region` to `entry` in every column overlapping it,
+    /// returns the number of columns changed.
+    pub fn fill(&mut self, region: &Region, entry: &Entry) -> Result<usize> {
+        if region.is_empty() {
+            return Ok(0);
+        }
+        let out_of_range = || {
+            Error::format(format!(
+                "blocks {}..{} out of range above min y {}",
+                region.y.start, region.y.end, self.min_y
+            ))
+        };
+        // Widened, the differences of extreme coordinates don't fit an i32
+        let start = u16::try_from(i64::from(region.y.start) - i64::from(self.min_y))
+            .ok()
+            .filter(|&y| y <= DataPoint::MAX_MIN_Y)
+            .ok_or_else(out_of_range)?;
+        let height = u16::try_from(i64::from(region.y.end) - i64::from(region.y.start))
+            .ok()
+            .filter(|&h| h <= DataPoint::MAX_HEIGHT)
+            .ok_or_else(out_of_range)?;
+        let y = start..start + height;
+
+        let pos = self.pos;
+        let no_columns =
+            || Error::format(format!("section {pos} is finer than a column per block"));
+        let width = columns::column_width(pos).ok_or_else(no_columns)?;
+        let changed = self.rewrite(None, |dx, dz, points, mapping| {
+            let (x, z) = columns::to_world(pos, dx, dz).ok_or_else(no_columns)?;
+            let overlaps = x < region.x.end
+                && x + width > region.x.start
+                && z < region.z.end
+                && z + width > region.z.start;
+            if overlaps {
+                paint(points, y.clone(), mapping.id(entry))?;
+            }
+            Ok(overlaps)
+        })?;
+
+        if changed > 0 {
+            self.apply_to_parent = Some(true);
+        }
+        Ok(changed)
+    }
+
+    /// Replaces every `from` block with `to`, returns the number of columns changed.
+    ///
+    /// Only exact matches are replaced, biome and block states included.
+    pub fn replace(&mut self, from: &Entry, to: &Entry) -> Result<usize> {
+        if from == to || self.mapping().is_some_and(|m| !m.iter().any(|e| e == from)) {
+            return Ok(0);
+        }
+
+        let changed = self.rewrite(Some((from, to)), |_, _, _, _| Ok(false))?;
+        if changed > 0 {
+            self.apply_to_parent = Some(true);
+        }
+        Ok(changed)
+    }
+
+    /// Rebuilds the data and mapping, `edit` changes the data points of a column
+    /// and returns whether it did. `replace` swaps one entry for another.
+    ///
+    /// Nothing is changed if it fails, returns the number of columns changed.
+    fn rewrite(
+        &mut self,
+        replace: Option<(&Entry, &Entry)>,
+        mut edit: impl FnMut(usize, usize, &mut Vec<DataPoint>, &mut MappingBuilder) -> Result<bool>,
+    ) -> Result<usize> {
+        let pos = self.pos;
+        let not_decompressed = || Error::format(format!("section {pos} is not decompressed"));
+        let (Some(data), Some(steps), Some(old)) =
+            (self.column_data(), self.world_gen_step(), self.mapping())
+        else {
+            return Err(not_decompressed());
+        };
+
+        // Entries are added as they're used, leaving out the unused ones
+        let mut mapping = MappingBuilder::new();
+        // New id of each old one and whether it was replaced
+        let mut ids = vec![None; old.len()];
+        let mut changed = 0;
+        let mut new_data = Vec::with_capacity(Columns::<()>::LEN);
+        let mut new_steps = Vec::with_capacity(Columns::<()>::LEN);
+        for (dx, dz, column, &step) in data.zip(steps) {
+            let mut replaced = false;
+            let mut points = Vec::with_capacity(column.len());
+            for point in column {
+                let old_id = point.id() as usize;
+                let (id, was_replaced) = match ids.get(old_id) {
+                    Some(Some(id)) => *id,
+                    _ => {
+                        let entry = old.get(old_id).ok_or_else(|| {
+                            Error::format(format!(
+                                "data point id {old_id} outside the mapping of section {pos}"
+                            ))
+                        })?;
+                        let id = match replace {
+                            Some((from, to)) if entry == from => (mapping.id(to), true),
+                            _ => (mapping.id(entry), false),
+                        };
+                        ids[old_id] = Some(id);
+                        id
+                    }
+                };
+                replaced |= was_replaced;
+                points.push(point.with_id(id));
+            }
+
+            let mut step = step;
+            if edit(dx, dz, &mut points, &mut mapping)? || replaced {
+                merge(&mut points)?;
+                if step == WorldGenStep::Empty && !points.is_empty() {
+                    step = WorldGenStep::Light;
+                }
+                changed += 1;
+            }
+            new_data.push(points.into_boxed_slice());
+            new_steps.push(step);
+        }
+
+        // An edit can overwrite the last data points of an entry
+        let mut mapping = mapping.build();
+        let mut used = vec![false; mapping.len()];
+        for point in new_data.iter().flat_map(|c| c.iter()) {
+            used[point.id() as usize] = true;
+        }
+        if used.contains(&false) {
+            let mut compact = MappingBuilder::new();
+            for point in new_data.iter_mut().flat_map(|c| c.iter_mut()) {
+                *point = point.with_id(compact.id(&mapping[&*point]));
+            }
+            mapping = compact.build();
+        }
+
+        let columns = |len| Error::format(format!("{len} columns off"));
+        let new_data = Columns::try_from_iter(new_data).map_err(columns)?;
+        let new_steps = Columns::try_from_iter(new_steps).map_err(columns)?;
+
+        let (Some(data), Some(steps), Some(old)) = (
+            self.data.as_mut(),
+            self.world_gen_step.as_mut(),
+            self.mapping.as_mut(),
+        ) else {
+            return Err(not_decompressed());
+        };
+        **data = new_data;
+        *steps = new_steps;
+        *old = mapping;
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DetailLevel,
+        block::Block,
+        section::{
+            builder::{Run, SectionBuilder},
+            pos::Pos,
+        },
+    };
+
+    /// Column `(0, 0)` is at block `(-64, 0)`.
+    const POS: Pos = Pos::new(DetailLevel::Chunk4, -1, 0);
+
+    fn entry(block: &str) -> Entry {
+        Entry::new("minecraft:plains", block)
+    }
+
+    /// A section with `runs` in column `(0, 0)` and stone up to 0 in every other one.
+    fn section(runs: &[(&str, Range<i32>)]) -> Section<'static> {
+        let mut builder = SectionBuilder::new(POS);
+        for dx in 0..Section::WIDTH {
+            for dz in 0..Section::WIDTH {
+                if (dx, dz) != (0, 0) {
+                    builder
+                        .push(dx, dz, Run::new(entry("minecraft:stone"), -64..0))
+                        .unwrap();
+                }
+            }
+        }
+        for (block, y) in runs {
+            builder
+                .push(0, 0, Run::new(entry(block), y.clone()))
+                .unwrap();
+        }
+        builder.build().unwrap()
+    }
+
+    /// Block and world heights of the data points of column `(0, 0)`, top down.
+    fn column(section: &Section<'_>) -> Vec<(String, Range<i32>)> {
+        let mapping = section.mapping().unwrap();
+        section
+            .column_data()
+            .unwrap()
+            .get(0, 0)
+            .iter()
+            .map(|p| {
+                let bottom = section.min_y + i32::from(p.min_y());
+                (
+                    mapping[p].full_identifier().to_string(),
+                    bottom..bottom + i32::from(p.height()),
+                )
+            })
+            .collect()
+    }
+
+    fn blocks(section: &Section<'_>) -> Vec<String> {
+        let mut blocks: Vec<_> = section
+            .mapping()
+            .unwrap()
+            .iter()
+            .map(|e| e.full_identifier().to_string())
+            .collect();
+        blocks.sort();
+        blocks
+    }
+
+    #[test]
+    fn paint_inside_one_point() {
+        let mut s = section(&[("minecraft:stone", -64..10)]);
+        s.set_block(-64, 0, 0, &entry("minecraft:dirt")).unwrap();
+        assert_eq!(
+            column(&s),
+            [
+                ("minecraft:stone".into(), 1..10),
+                ("minecraft:dirt".into(), 0..1),
+                ("minecraft:stone".into(), -64..0),
+            ]
+        );
+        assert_eq!(s.apply_to_parent, Some(true));
+    }
+
+    #[test]
+    fn paint_across_two_points() {
+        let mut s = section(&[("minecraft:stone", -64..0), ("minecraft:dirt", 0..10)]);
+        let region = Region::new(-64..-63, -2..3, 0..1);
+        assert_eq!(s.fill(&region, &entry("minecraft:sand")).unwrap(), 1);
+        assert_eq!(
+            column(&s),
+            [
+                ("minecraft:dirt".into(), 3..10),
+                ("minecraft:sand".into(), -2..3),
+                ("minecraft:stone".into(), -64..-2),
+            ]
+        );
+    }
+
+    #[test]
+    fn paint_into_gap() {
+        let mut s = section(&[("minecraft:stone", -64..0), ("minecraft:dirt", 20..21)]);
+        s.set_block(-64, 5, 0, &entry("minecraft:sand")).unwrap();
+        assert_eq!(
+            column(&s),
+            [
+                ("minecraft:dirt".into(), 20..21),
+                ("minecraft:sand".into(), 5..6),
+                ("minecraft:stone".into(), -64..0),
+            ]
+        );
+        let sand = s.column_data().unwrap().get(0, 0)[1];
+        assert_eq!(sand.sky_light(), LightLevel::SUN);
+    }
+
+    #[test]
+    fn fill_merges_with_neighbours() {
+        let mut s = section(&[("minecraft:stone", -64..0), ("minecraft:stone", 5..10)]);
+        s.fill(
+            &Region::new(-64..-63, 0..5, 0..1),
+            &entry("minecraft:stone"),
+        )
+        .unwrap();
+        assert_eq!(column(&s), [("minecraft:stone".into(), -64..10)]);
+    }
+
+    #[test]
+    fn replace_merges() {
+        let mut s = section(&[
+            ("minecraft:stone", -64..0),
+            ("minecraft:dirt", 0..5),
+            ("minecraft:stone", 5..10),
+        ]);
+        assert_eq!(blocks(&s), ["minecraft:dirt", "minecraft:stone"]);
+        let changed = s
+            .replace(&entry("minecraft:dirt"), &entry("minecraft:stone"))
+            .unwrap();
+        assert_eq!(changed, 1);
+        assert_eq!(column(&s), [("minecraft:stone".into(), -64..10)]);
+        // Dirt isn't used anymore
+        assert_eq!(blocks(&s), ["minecraft:stone"]);
+
+        assert_eq!(
+            s.replace(&entry("minecraft:dirt"), &entry("minecraft:sand"))
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn overwritten_entry_is_dropped() {
+        let mut s = section(&[("minecraft:stone", -64..0), ("minecraft:dirt", 0..1)]);
+        s.set_block(-64, 0, 0, &entry("minecraft:sand")).unwrap();
+        assert_eq!(blocks(&s), ["minecraft:sand", "minecraft:stone"]);
+        let mapping = s.mapping().unwrap();
+        for (_, _, points) in s.column_data().unwrap().iter() {
+            assert!(points.iter().all(|p| (p.id() as usize) < mapping.len()));
+        }
+    }
+
+    #[test]
+    fn failed_edit_changes_nothing() {
+        let mut s = section(&[("minecraft:stone", -64..10)]);
+        // Broken data in the last column, found after the first one was edited
+        let last = s.data.as_mut().unwrap().get_mut(63, 63);
+        last[0] = last[0].with_id(7);
+        let before = column(&s);
+
+        let everything = Region::new(-64..0, 0..1, 0..64);
+        assert!(s.fill(&everything, &entry("minecraft:dirt")).is_err());
+        assert!(
+            s.replace(&entry("minecraft:stone"), &entry("minecraft:dirt"))
+                .is_err()
+        );
+        assert_eq!(column(&s), before);
+        assert_eq!(blocks(&s), ["minecraft:stone"]);
+        assert_eq!(s.column_data().unwrap().get(63, 63)[0].id(), 7);
+
+        // Out of range heights fail before anything is read
+        let mut s = section(&[("minecraft:stone", -64..10)]);
+        assert!(s.set_block(-64, -65, 0, &entry("minecraft:dirt")).is_err());
+        assert!(s.set_block(0, 0, 0, &entry("minecraft:dirt")).is_err());
+        assert_eq!(column(&s), [("minecraft:stone".into(), -64..10)]);
+    }
+
+    #[test]
+    fn extreme_coordinates() {
+        assert_eq!(Region::block(i32::MAX, 0, 0), None);
+        assert_eq!(Region::block(0, i32::MAX, 0), None);
+        assert_eq!(Region::block(0, 0, i32::MAX), None);
+        assert_eq!(
+            Region::block(i32::MIN, -1, 0),
+            Some(Region::new(i32::MIN..i32::MIN + 1, -1..0, 0..1))
+        );
+
+        let mut s = section(&[("minecraft:stone", -64..10)]);
+        let dirt = entry("minecraft:dirt");
+        for y in [i32::MIN, i32::MAX] {
+            assert!(matches!(
+                s.set_block(-64, y, 0, &dirt),
+                Err(Error::Format(_))
+            ));
+        }
+        for y in [
+            i32::MIN..i32::MAX,
+            i32::MIN..0,
+            0..i32::MAX,
+            i32::MAX - 1..i32::MAX,
+        ] {
+            let region = Region::new(-64..-63, y, 0..1);
+            assert!(matches!(s.fill(&region, &dirt), Err(Error::Format(_))));
+        }
+        assert_eq!(column(&s), [("minecraft:stone".into(), -64..10)]);
+    }
+}
@@ -1,8 +1,8 @@
 // https://gitlab.com/distant-horizons-team/distant-horizons-core/-/blob/main/core/src/main/java/com/seibel/distanthorizons/core/dataObjects/fullData/FullDataPointIdMap.java
 
 use std::{
-    collections::{BTreeMap, BTreeSet},
-    sync::RwLock,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{PoisonError, RwLock},
 };
 
 use smol_str::SmolStr;
@@ -21,18 +21,18 @@ fn intern(value: &str, set: &RwLock<BTreeSet<SmolStr>>) -> SmolStr {
     if value.len() <= 23 {
         return value.into();
     }
-    let read = set.read().unwrap();
+    let read = set.read().unwrap_or_else(PoisonError::into_inner);
     if let Some(value) = read.get("") {
         return value.clone();
     }
     drop(read);
-    let mut write = set.write().unwrap();
+    let mut write = set.write().unwrap_or_else(PoisonError::into_inner);
     let value: SmolStr = value.into();
     write.insert(value.clone());
     value
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Entry {
     biome: SmolStr,
     block: SmolStr,
@@ -42,11 +42,42 @@ pub struct Entry {
 }
 
 impl Entry {
+    /// `block` in `biome`, both with their namespace like `minecraft:stone`, without any state.
+    #[inline]
+    #[must_use]
+    pub fn new(biome: &str, block: &str) -> Self {
+        Self {
+            biome: intern(biome, &BIOMES),
+            block: intern(block, &BLOCKS),
+            state: BTreeMap::new(),
+            #[cfg(test)]
+            raw: format!("{biome}{BLOCK_STATE_SEPARATOR_STRING}{block}"),
+        }
+    }
+
+    /// Adds or replaces a block state property.
+    #[inline]
+    #[must_use]
+    pub fn with_state(mut self, key: &str, value: &str) -> Self {
+        self.state
+            .insert(intern(key, &STATE_KEYS), intern(value, &STATE_VALUES));
+        #[cfg(test)]
+        {
+            self.raw = self.to_string();
+        }
+        self
+    }
+
     #[inline]
     pub fn biome(&self) -> &str {
         self.biome.as_str()
     }
 
+    #[inline]
+    pub fn states(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.state.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
     #[inline]
     pub fn in_nether(&self) -> bool {
         matches!(
@@ -85,15 +116,36 @@ impl Default for Entry {
     }
 }
 
+/// The string DH stores in the mapping, `biome_DH-BSW_block_STATE_{key:value}...`.
+impl core::fmt::Display for Entry {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{}{BLOCK_STATE_SEPARATOR_STRING}{}",
+            self.biome, self.block
+        )?;
+        if !self.state.is_empty() {
+            f.write_str("_STATE_")?;
+            for (key, value) in &self.state {
+                write!(f, "{{{key}:{value}}}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the entry string of data format version 1, the only one DH wrote so far.
+/// [`Section::decompress`](super::Section::decompress) rejects other versions first.
 impl TryFrom<String> for Entry {
-    type Error = anyhow::Error;
+    type Error = crate::Error;
 
     #[inline]
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        use anyhow::Context;
+        let invalid = |reason| crate::Error::format(format!("{reason} in entry {value:?}"));
         let (biome, block_state) = value
             .split_once(BLOCK_STATE_SEPARATOR_STRING)
-            .context("missing separator after biome")?;
+            .ok_or_else(|| invalid("missing separator after biome"))?;
 
         let (block, state_str) = block_state
             .split_once("_STATE_")
@@ -119,9 +171,11 @@ impl TryFrom<String> for Entry {
                 continue;
             }
             let Some(("{", kv)) = kv.split_at_checked(1) else {
-                anyhow::bail!("missing opening brace in state");
+                return Err(invalid("missing opening brace in state"));
             };
-            let (key, value) = kv.split_once(':').context("missing separator in state")?;
+            let (key, value) = kv
+                .split_once(':')
+                .ok_or_else(|| invalid("missing separator in state"))?;
             let key = intern(key, &STATE_KEYS);
             let value = intern(value, &STATE_VALUES);
             state.insert(key, value);
@@ -140,6 +194,64 @@ impl TryFrom<String> for Entry {
 #[derive(Debug)]
 pub struct Mapping(Box<[Entry]>);
 
+impl Mapping {
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &Entry> {
+        self.0.iter()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get(&self, id: usize) -> Option<&Entry> {
+        self.0.get(id)
+    }
+}
+
+/// Collects entries into a [`Mapping`], like DH's `FullDataPointIdMap::addIfNotPresentAndGetId`.
+#[derive(Debug, Default)]
+pub struct MappingBuilder {
+    entries: Vec<Entry>,
+    ids: HashMap<Entry, u32>,
+}
+
+impl MappingBuilder {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The id of `entry`, it is added if it isn't in the mapping yet.
+    #[inline]
+    pub fn id(&mut self, entry: &Entry) -> u32 {
+        if let Some(&id) = self.ids.get(entry) {
+            return id;
+        }
+        let id = u32::try_from(self.entries.len()).expect("more than u32::MAX mapping entries");
+        self.entries.push(entry.clone());
+        self.ids.insert(entry.clone(), id);
+        id
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn build(self) -> Mapping {
+        Mapping(self.entries.into_boxed_slice())
+    }
+}
+
 impl core::ops::Index<usize> for Mapping {
     type Output = Entry;
 
@@ -160,7 +272,7 @@ impl core::ops::Index<&super::data::DataPoint> for Mapping {
 }
 
 impl TryFrom<Box<[u8]>> for Mapping {
-    type Error = anyhow::Error;
+    type Error = crate::Error;
 
     #[inline]
     fn try_from(data: Box<[u8]>) -> Result<Self, Self::Error> {
@@ -168,8 +280,9 @@ impl TryFrom<Box<[u8]>> for Mapping {
         let mut data = data.as_ref();
 
         let mut count = [0; 4];
-        data.read_exact(&mut count)?;
-        let count: usize = u32::from_be_bytes(count).try_into()?;
+        data.read_exact(&mut count)
+            .map_err(|_| crate::Error::format("missing mapping length"))?;
+        let count = u32::from_be_bytes(count) as usize;
 
         let mut entries = Vec::with_capacity(count);
         for _ in 0..count {
@@ -178,20 +291,98 @@ impl TryFrom<Box<[u8]>> for Mapping {
             entries.push(entry);
         }
 
-        anyhow::ensure!(data.is_empty(), "data not fully consumed");
+        if !data.is_empty() {
+            return Err(crate::Error::format("mapping not fully consumed"));
+        }
 
         Ok(Self(entries.into_boxed_slice()))
     }
 }
 
+impl TryFrom<&Mapping> for Box<[u8]> {
+    type Error = crate::Error;
+
+    #[inline]
+    fn try_from(mapping: &Mapping) -> Result<Self, Self::Error> {
+        let count = u32::try_from(mapping.len())
+            .map_err(|_| crate::Error::format("too many mapping entries"))?;
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&count.to_be_bytes());
+        for entry in mapping.iter() {
+            crate::java::writeUTF(&mut data, &entry.to_string())?;
+        }
+        Ok(data.into_boxed_slice())
+    }
+}
+
 pub fn print_interned_sizes() {
-    println!("biomes: {}", BIOMES.read().unwrap().len());
-    println!("blocks: {}", BLOCKS.read().unwrap().len());
-    println!("state keys: {}", STATE_KEYS.read().unwrap().len());
-    println!("state values: {}", STATE_VALUES.read().unwrap().len());
-
-    println!("biomes: {:?}", BIOMES.read().unwrap());
-    println!("blocks: {:?}", BLOCKS.read().unwrap());
-    println!("state keys: {:?}", STATE_KEYS.read().unwrap());
-    println!("state values: {:?}", STATE_VALUES.read().unwrap());
+    println!(
+        "biomes: {}",
+        BIOMES.read().unwrap_or_else(PoisonError::into_inner).len()
+    );
+    println!(
+        "blocks: {}",
+        BLOCKS.read().unwrap_or_else(PoisonError::into_inner).len()
+    );
+    println!(
+        "state keys: {}",
+        STATE_KEYS
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    );
+    println!(
+        "state values: {}",
+        STATE_VALUES
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    );
+
+    println!(
+        "biomes: {:?}",
+        BIOMES.read().unwrap_or_else(PoisonError::into_inner)
+    );
+    println!(
+        "blocks: {:?}",
+        BLOCKS.read().unwrap_or_else(PoisonError::into_inner)
+    );
+    println!(
+        "state keys: {:?}",
+        STATE_KEYS.read().unwrap_or_else(PoisonError::into_inner)
+    );
+    println!(
+        "state values: {:?}",
+        STATE_VALUES.read().unwrap_or_else(PoisonError::into_inner)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_string() {
+        let raw =
+            "minecraft:taiga_DH-BSW_minecraft:spruce_leaves_STATE_{distance:1}{waterlogged:false}";
+        let entry = Entry::try_from(raw.to_string()).unwrap();
+        assert_eq!(entry.biome(), "minecraft:taiga");
+        assert_eq!(entry.full_identifier(), "minecraft:spruce_leaves");
+        assert_eq!(entry.get_state("distance"), Some("1"));
+        assert_eq!(entry.to_string(), raw);
+    }
+
+    #[test]
+    fn other_entry_formats_are_rejected() {
+        for raw in [
+            "minecraft:stone",
+            "minecraft:plains|minecraft:stone",
+            "minecraft:plains_DH-BSW_minecraft:stone_STATE_[axis=y]",
+            "minecraft:plains_DH-BSW_minecraft:stone_STATE_{axis=y}",
+        ] {
+            let error = Entry::try_from(raw.to_string()).unwrap_err().to_string();
+            assert!(error.contains(raw), "{error}");
+        }
+    }
 }
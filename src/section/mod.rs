@@ -1,11 +1,14 @@
-use anyhow::Context;
-use duckdb::Row;
+use crate::backend::Row;
 
+pub mod builder;
 pub mod columns;
 pub mod compression;
 pub mod data;
+mod downsample;
+pub mod edit;
 pub mod mapping;
 pub mod pos;
+pub mod tree;
 pub mod world_gen_step;
 
 use columns::Columns;
@@ -62,6 +65,45 @@ impl Section<'_> {
         self.last_modified
     }
 
+    #[inline]
+    #[must_use]
+    pub const fn created(&self) -> i64 {
+        self.created
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn checksum(&self) -> i32 {
+        self.checksum
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn format_version(&self) -> u8 {
+        self.format_version
+    }
+
+    /// Whether DH still has to update the parent from this section.
+    #[inline]
+    #[must_use]
+    pub const fn apply_to_parent(&self) -> bool {
+        matches!(self.apply_to_parent, Some(true))
+    }
+
+    /// Whether DH still has to update the children from this section.
+    #[inline]
+    #[must_use]
+    pub const fn apply_to_children(&self) -> bool {
+        matches!(self.apply_to_children, Some(true))
+    }
+
+    /// Marks the section as propagated in both directions.
+    #[inline]
+    pub const fn clear_propagation(&mut self) {
+        self.apply_to_parent = Some(false);
+        self.apply_to_children = Some(false);
+    }
+
     #[inline]
     #[must_use]
     pub fn column_data(&self) -> Option<&Columns<Box<[data::DataPoint]>>> {
@@ -75,49 +117,130 @@ impl Section<'_> {
     }
 
     #[inline]
-    pub fn get_all_from_db(db_path: impl AsRef<str>) -> Result<Vec<Self>, duckdb::Error> {
-        let conn = duckdb::Connection::open_in_memory()?;
-        conn.execute("INSTALL SQLITE", [])?;
-        conn.execute(
-            &format!(
-                "ATTACH '{}' AS dh (TYPE SQLITE, READONLY)",
-                db_path.as_ref()
-            ),
-            [],
-        )?;
-        conn.execute("SET sqlite_all_varchar=true", [])?;
-        conn.execute("USE dh", [])?;
+    #[must_use]
+    pub fn world_gen_step(&self) -> Option<&Columns<world_gen_step::WorldGenStep>> {
+        self.world_gen_step.as_ref()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn world_compression(&self) -> Option<&Columns<compression::WorldCompression>> {
+        self.world_compression.as_ref()
+    }
+
+    pub fn get(
+        conn: &crate::backend::Connection,
+        pos: Pos,
+    ) -> Result<Option<Self>, crate::backend::Error> {
+        use crate::repo::{Repo, WritableRepo};
+
+        let mut found = Self::select_vec_with(conn, &Self::by_key(), pos, |s| s.into_owned())?;
+        Ok(found.pop())
+    }
+
+    /// Calls `f` for every section, optionally only those of one detail level,
+    /// without loading them all into memory.
+    pub fn for_each<E: From<crate::backend::Error>>(
+        conn: &crate::backend::Connection,
+        detail_level: Option<crate::DetailLevel>,
+        f: impl for<'r> FnMut(Section<'r>) -> Result<(), E>,
+    ) -> Result<(), E> {
+        use crate::repo::{All, Repo};
+
+        struct Q;
+
+        impl Query<crate::DetailLevel> for Q {
+            fn r#where(&self) -> &str {
+                "DetailLevel = ?"
+            }
+
+            fn bind_params(
+                &self,
+                stmt: &mut crate::backend::Statement,
+                detail_level: crate::DetailLevel,
+            ) -> crate::backend::Result<()> {
+                stmt.raw_bind_parameter(1, detail_level - Pos::SECTION_MINIMUM_DETAIL_LEVEL)?;
+                Ok(())
+            }
+        }
+
+        match detail_level {
+            Some(detail_level) => Self::select_each_with(conn, &Q, detail_level, f),
+            None => Self::select_each_with(conn, &All, (), f),
+        }
+    }
+
+    /// Inserts the section, it must not exist in the database yet.
+    #[inline]
+    pub fn insert_into(
+        self,
+        conn: &crate::backend::Connection,
+    ) -> Result<(), crate::backend::Error> {
+        use crate::repo::WritableRepo;
+
+        Self::insert(conn, self)
+    }
+
+    /// Inserts the section, replacing the one at the same position.
+    #[inline]
+    pub fn upsert_into(
+        self,
+        conn: &crate::backend::Connection,
+    ) -> Result<(), crate::backend::Error> {
+        use crate::repo::WritableRepo;
+
+        Self::upsert(conn, self)
+    }
+
+    /// Deletes the section at `pos`, returns whether it existed.
+    #[inline]
+    pub fn delete(
+        conn: &crate::backend::Connection,
+        pos: Pos,
+    ) -> Result<bool, crate::backend::Error> {
+        use crate::repo::WritableRepo;
+
+        <Self as WritableRepo>::delete(conn, pos)
+    }
+
+    #[inline]
+    pub fn get_all_from_db(db_path: impl AsRef<std::path::Path>) -> crate::Result<Vec<Self>> {
+        let conn = crate::open_database(db_path, crate::database::Mode::ReadOnly)?;
 
-        Self::get_all(&conn)
+        Ok(Self::get_all(&conn)?)
     }
 
     pub fn get_all_with_detail_level_modified_after(
-        conn: &duckdb::Connection,
+        conn: &crate::backend::Connection,
         detail_level: crate::DetailLevel,
         last_modified: i64,
-    ) -> Result<Vec<Self>, duckdb::Error> {
+    ) -> Result<Vec<Self>, crate::backend::Error> {
         use crate::repo::Repo;
 
-        struct Q;
+        struct Q(String);
 
         impl Query<(crate::DetailLevel, i64)> for Q {
             fn r#where(&self) -> &str {
-                "DetailLevel = ? AND CAST(lastModifiedUnixDateTime AS BIGINT) > ?"
+                &self.0
             }
 
             fn bind_params(
                 &self,
-                stmt: &mut duckdb::Statement,
+                stmt: &mut crate::backend::Statement,
                 (detail_level, last_modified): (crate::DetailLevel, i64),
-            ) {
-                stmt.raw_bind_parameter(1, detail_level).unwrap();
-                stmt.raw_bind_parameter(2, last_modified).unwrap();
+            ) -> crate::backend::Result<()> {
+                stmt.raw_bind_parameter(1, detail_level)?;
+                stmt.raw_bind_parameter(2, last_modified)?;
+                Ok(())
             }
         }
 
         Self::select_vec_with(
             &conn,
-            &Q,
+            &Q(format!(
+                "DetailLevel = ? AND {} > ?",
+                crate::backend::LAST_MODIFIED
+            )),
             (
                 detail_level - Pos::SECTION_MINIMUM_DETAIL_LEVEL,
                 last_modified,
@@ -127,10 +250,13 @@ impl Section<'_> {
     }
 
     #[inline]
-    pub fn get_all(conn: &duckdb::Connection) -> Result<Vec<Self>, duckdb::Error> {
+    pub fn get_all(conn: &crate::backend::Connection) -> Result<Vec<Self>, crate::backend::Error> {
         use crate::repo::{All, Repo};
 
-        let q = All.ordered("(cast(PosX as INTEGER) ** 2 + cast(PosZ as INTEGER) ** 2) DESC");
+        let q = All.ordered(
+            "CAST(PosX AS INTEGER) * CAST(PosX AS INTEGER) \
+             + CAST(PosZ AS INTEGER) * CAST(PosZ AS INTEGER) DESC",
+        );
 
         Self::select_vec(conn, &q, |s| s.into_owned())
     }
@@ -172,15 +298,61 @@ impl Section<'_> {
     }
 
     #[inline]
-    pub fn decompress(&mut self) -> Result<(), anyhow::Error> {
-        self.data.decompress().context("decompressing data")?;
+    pub fn decompress(&mut self) -> crate::Result<()> {
+        let pos = self.pos;
+        let context = |field| {
+            move |source| crate::Error::Section {
+                pos,
+                field,
+                source: Box::new(source),
+            }
+        };
+
+        if !crate::schema::is_supported_format_version(self.format_version) {
+            return Err(context("format")(crate::Error::UnsupportedVersion {
+                what: "data format",
+                version: self.format_version.into(),
+            }));
+        }
+
+        self.data.decompress().map_err(context("data"))?;
         self.world_gen_step
             .decompress()
-            .context("decompressing world_gen_steps")?;
+            .map_err(context("world generation steps"))?;
         self.world_compression
             .decompress()
-            .context("decompressing world compression")?;
-        self.mapping.decompress().context("decompressing mapping")?;
+            .map_err(context("world compression"))?;
+        self.mapping.decompress().map_err(context("mapping"))?;
+
+        Ok(())
+    }
+
+    /// Encodes and compresses the fields that were changed or built in memory,
+    /// so the section can be written to the database.
+    #[inline]
+    pub fn compress(&mut self) -> crate::Result<()> {
+        let pos = self.pos;
+        let context = |field| {
+            move |source| crate::Error::Section {
+                pos,
+                field,
+                source: Box::new(source),
+            }
+        };
+
+        let changed = !self.data.is_compressed();
+        let data = self.data.compress().map_err(context("data"))?;
+        if changed {
+            // Only compared for equality, see `diff`
+            self.checksum = crate::java::hashCode(data);
+        }
+        self.world_gen_step
+            .compress()
+            .map_err(context("world generation steps"))?;
+        self.world_compression
+            .compress()
+            .map_err(context("world compression"))?;
+        self.mapping.compress().map_err(context("mapping"))?;
 
         Ok(())
     }
@@ -193,6 +365,57 @@ impl Section<'_> {
         self.mapping.drop_cache();
     }
 
+    /// Drops the decompressed data, keeping the compressed buffers to decompress it again later.
+    #[inline]
+    pub fn drop_decompressed(&mut self) {
+        self.data.drop_value();
+        self.world_gen_step.drop_value();
+        self.world_compression.drop_value();
+        self.mapping.drop_value();
+    }
+
+    /// Approximate number of bytes held by the compressed buffers and decompressed data.
+    #[inline]
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        self.compressed_size() + self.decompressed_size()
+    }
+
+    /// Number of bytes of the compressed buffers, as stored in the database.
+    #[inline]
+    #[must_use]
+    pub fn compressed_size(&self) -> usize {
+        self.data.compressed_len()
+            + self.world_gen_step.compressed_len()
+            + self.world_compression.compressed_len()
+            + self.mapping.compressed_len()
+    }
+
+    /// Approximate number of bytes held by the decompressed data, zero while compressed.
+    #[inline]
+    #[must_use]
+    pub fn decompressed_size(&self) -> usize {
+        use core::mem::size_of;
+
+        let mut size = 0;
+        if let Some(cols) = self.column_data() {
+            let points: usize = cols.as_ref().iter().map(|col| col.len()).sum();
+            size += size_of::<Columns<Box<[data::DataPoint]>>>();
+            size += points * size_of::<data::DataPoint>();
+        }
+        if self.world_gen_step().is_some() {
+            size += size_of::<Columns<world_gen_step::WorldGenStep>>();
+        }
+        if self.world_compression().is_some() {
+            size += size_of::<Columns<compression::WorldCompression>>();
+        }
+        if let Some(mapping) = self.mapping() {
+            size += mapping.len() * size_of::<mapping::Entry>();
+        }
+
+        size
+    }
+
     #[inline]
     #[must_use]
     pub const fn block_width(&self) -> i32 {
@@ -208,33 +431,11 @@ impl Section<'_> {
 
 impl crate::repo::Repo for Section<'_> {
     const TABLE: &'static str = "FullData";
-    const INSERT: &'static str = "
-            BY NAME (
-                SELECT
-                    ? AS DetailLevel,
-                    ? AS PosX,
-                    ? AS PosZ,
-                    ? AS MinY,
-                    ? AS DataChecksum,
-                    ? AS Data,
-                    ? AS ColumnGenerationStep,
-                    ? AS ColumnWorldCompressionMode,
-                    ? AS Mapping,
-                    ? AS DataFormatVersion,
-                    ? AS CompressionMode,
-                    ? AS ApplyToParent,
-                    ? AS LastModifiedUnixDateTime,
-                    ? AS CreatedUnixDateTime,
-
-                    -- ? AS ApplyToChildren,
-            );
-        ";
-
     type Element<'r> = Section<'r>;
 
     /// <https://gitlab.com/distant-horizons-team/distant-horizons-core/-/blob/main/core/src/main/java/com/seibel/distanthorizons/core/sql/repo/FullDataSourceV2Repo.java>
     #[inline]
-    fn from_row<'r>(row: &'r Row) -> duckdb::Result<Self::Element<'r>> {
+    fn from_row<'r>(row: &'r Row) -> crate::backend::Result<Self::Element<'r>> {
         let mut detail_level = row.get("DetailLevel")?;
         detail_level = detail_level + Pos::SECTION_MINIMUM_DETAIL_LEVEL;
         let x: i32 = row.get("PosX")?;
@@ -280,9 +481,65 @@ impl crate::repo::Repo for Section<'_> {
             created,
         })
     }
+}
+
+impl crate::repo::WritableRepo for Section<'_> {
+    const INSERT: &'static str = "
+            (
+                DetailLevel,
+                PosX,
+                PosZ,
+                MinY,
+                DataChecksum,
+                Data,
+                ColumnGenerationStep,
+                ColumnWorldCompressionMode,
+                Mapping,
+                DataFormatVersion,
+                CompressionMode,
+                ApplyToParent,
+                ApplyToChildren,
+                LastModifiedUnixDateTime,
+                CreatedUnixDateTime
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ";
+
+    type Key = Pos;
+
+    #[inline]
+    fn key(sec: &Self::Element<'_>) -> Pos {
+        sec.pos
+    }
+
+    #[inline]
+    fn by_key() -> impl Query<Pos> {
+        struct Q;
+
+        impl Query<Pos> for Q {
+            fn r#where(&self) -> &str {
+                "DetailLevel = ? AND PosX = ? AND PosZ = ?"
+            }
+
+            fn bind_params(
+                &self,
+                stmt: &mut crate::backend::Statement,
+                pos: Pos,
+            ) -> crate::backend::Result<()> {
+                stmt.raw_bind_parameter(1, pos.detail_level - Pos::SECTION_MINIMUM_DETAIL_LEVEL)?;
+                stmt.raw_bind_parameter(2, pos.x)?;
+                stmt.raw_bind_parameter(3, pos.z)?;
+                Ok(())
+            }
+        }
+
+        Q
+    }
 
     #[inline]
-    fn bind_insert(stmt: &mut duckdb::Statement, sec: Self::Element<'_>) -> duckdb::Result<()> {
+    fn bind_insert(
+        stmt: &mut crate::backend::Statement,
+        sec: Self::Element<'_>,
+    ) -> crate::backend::Result<()> {
         macro_rules! bind {
             (@param $i:ident = $p:expr) => {
                 $i += 1;
@@ -312,6 +569,7 @@ impl crate::repo::Repo for Section<'_> {
             sec.format_version,
             sec.compression,
             sec.apply_to_parent,
+            sec.apply_to_children,
             sec.last_modified,
             sec.created
         ];
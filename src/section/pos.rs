@@ -9,9 +9,126 @@ pub struct Pos {
     pub z: i32,
 }
 
+/// Shifts a coordinate from one detail level to another.
+///
+/// Towards a coarser level this is the position containing it,
+/// towards a finer one the position at its minimum corner.
+#[inline]
+const fn convert(value: i32, from: DetailLevel, to: DetailLevel) -> i32 {
+    let (from, to) = (from as u8, to as u8);
+    if to >= from {
+        value >> (to - from)
+    } else {
+        value << (from - to)
+    }
+}
+
 impl Pos {
     pub const SECTION_MINIMUM_DETAIL_LEVEL: DetailLevel = DetailLevel::Chunk4;
 
+    #[inline]
+    #[must_use]
+    pub const fn new(detail_level: DetailLevel, x: i32, z: i32) -> Self {
+        Self { detail_level, x, z }
+    }
+
+    /// The position at `detail_level` containing the block.
+    #[inline]
+    #[must_use]
+    pub const fn from_block(detail_level: DetailLevel, x: i32, z: i32) -> Self {
+        Self::new(DetailLevel::Block, x, z).to_detail_level(detail_level)
+    }
+
+    /// The position at `detail_level` containing the chunk,
+    /// or at its minimum corner if `detail_level` is finer than a chunk.
+    #[inline]
+    #[must_use]
+    pub const fn from_chunk(detail_level: DetailLevel, x: i32, z: i32) -> Self {
+        Self::new(DetailLevel::Chunk, x, z).to_detail_level(detail_level)
+    }
+
+    /// The position at `detail_level` containing the region,
+    /// or at its minimum corner if `detail_level` is finer than a region.
+    #[inline]
+    #[must_use]
+    pub const fn from_region(detail_level: DetailLevel, x: i32, z: i32) -> Self {
+        Self::new(DetailLevel::Region, x, z).to_detail_level(detail_level)
+    }
+
+    /// The same area at another detail level, see [`Self::from_chunk`] for finer levels.
+    #[inline]
+    #[must_use]
+    pub const fn to_detail_level(self, detail_level: DetailLevel) -> Self {
+        Self {
+            detail_level,
+            x: convert(self.x, self.detail_level, detail_level),
+            z: convert(self.z, self.detail_level, detail_level),
+        }
+    }
+
+    /// The position one detail level coarser containing this one, `None` at the coarsest level.
+    #[inline]
+    #[must_use]
+    pub const fn parent(self) -> Option<Self> {
+        match self.detail_level.coarser() {
+            Some(detail_level) => Some(self.to_detail_level(detail_level)),
+            None => None,
+        }
+    }
+
+    /// The four positions one detail level finer covering this one,
+    /// ordered `(0, 0)`, `(1, 0)`, `(0, 1)`, `(1, 1)` like DH's child indices.
+    /// `None` at [`DetailLevel::Block`].
+    ///
+    /// Sections aren't stored below [`Self::SECTION_MINIMUM_DETAIL_LEVEL`],
+    /// the children of such a section are only useful as areas.
+    #[inline]
+    #[must_use]
+    pub const fn children(self) -> Option<[Self; 4]> {
+        let Some(detail_level) = self.detail_level.finer() else {
+            return None;
+        };
+        let Self { x, z, .. } = self.to_detail_level(detail_level);
+
+        Some([
+            Self::new(detail_level, x, z),
+            Self::new(detail_level, x + 1, z),
+            Self::new(detail_level, x, z + 1),
+            Self::new(detail_level, x + 1, z + 1),
+        ])
+    }
+
+    /// The adjacent positions at the same detail level: north (-z), south (+z), west (-x), east (+x).
+    #[inline]
+    #[must_use]
+    pub const fn neighbours(self) -> [Self; 4] {
+        let Self { detail_level, x, z } = self;
+        [
+            Self::new(detail_level, x, z - 1),
+            Self::new(detail_level, x, z + 1),
+            Self::new(detail_level, x - 1, z),
+            Self::new(detail_level, x + 1, z),
+        ]
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn contains_block(&self, x: i32, z: i32) -> bool {
+        let pos = Self::from_block(self.detail_level, x, z);
+        pos.x == self.x && pos.z == self.z
+    }
+
+    /// Whether `other` lies inside this position, at this or any finer detail level.
+    #[inline]
+    #[must_use]
+    pub const fn contains(&self, other: Self) -> bool {
+        if (other.detail_level as u8) > (self.detail_level as u8) {
+            return false;
+        }
+        let pos = other.to_detail_level(self.detail_level);
+        pos.x == self.x && pos.z == self.z
+    }
+
     #[inline]
     #[must_use]
     pub const fn center_x(&self) -> i32 {
@@ -75,169 +192,362 @@ impl core::fmt::Display for Pos {
     }
 }
 
-// pub const DETAIL_LEVEL_WIDTH: usize = 8;
-// pub const X_POS_WIDTH: usize = 28;
-// pub const Z_POS_WIDTH: usize = 28;
-// pub const X_POS_MISSING_WIDTH: usize = 32 - 28;
-// pub const Z_POS_MISSING_WIDTH: usize = 32 - 28;
-
-// pub const DETAIL_LEVEL_OFFSET: usize = 0;
-// pub const POS_X_OFFSET: usize = DETAIL_LEVEL_OFFSET + DETAIL_LEVEL_WIDTH;
-// pub const POS_Z_OFFSET: usize = POS_X_OFFSET + X_POS_WIDTH;
-
-// pub const DETAIL_LEVEL_MASK: i64 = u8::MAX as i64;
-// pub const POS_X_MASK: i32 = (1 << X_POS_WIDTH) - 1;
-// pub const POS_Z_MASK: i32 = (1 << Z_POS_WIDTH) - 1;
-
-// #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-// pub struct SectionPosLegazy(i64);
-
-// impl SectionPosLegazy {
-//     pub const SECTION_MINIMUM_DETAIL_LEVEL: DetailLevel = DetailLevel::Chunk4;
-
-//     #[inline]
-//     pub const fn new(section_detail_level: DetailLevel, x: i32, z: i32) -> Self {
-//         assert!(x.unsigned_abs() < 1 << X_POS_WIDTH);
-//         assert!(z.unsigned_abs() < 1 << Z_POS_WIDTH);
-
-//         let mut data = 0;
-//         data |= section_detail_level as i64 & DETAIL_LEVEL_MASK;
-//         data |= ((x & POS_X_MASK) as i64) << POS_X_OFFSET;
-//         data |= ((z & POS_Z_MASK) as i64) << POS_Z_OFFSET;
-
-//         Self(data)
-//     }
-
-//     #[inline]
-//     pub const fn detail_level(self) -> DetailLevel {
-//         let dl = ((self.0 >> DETAIL_LEVEL_OFFSET) & DETAIL_LEVEL_MASK) as u8;
-//         assert!(dl <= DetailLevel::MAX as u8);
-//         unsafe { core::mem::transmute(dl) }
-//     }
-
-//     #[inline]
-//     pub const fn x(self) -> i32 {
-//         let x = ((self.0 >> POS_X_OFFSET) & POS_X_MASK as i64) as i32;
-//         // if at least one of the first 4 bits is neither 0 for positive nor 1 for negative, this won't work
-//         (x << X_POS_MISSING_WIDTH) >> X_POS_MISSING_WIDTH
-//     }
-
-//     #[inline]
-//     pub const fn z(self) -> i32 {
-//         let z = ((self.0 >> POS_Z_OFFSET) & POS_Z_MASK as i64) as i32;
-//         (z << Z_POS_MISSING_WIDTH) >> Z_POS_MISSING_WIDTH
-//     }
-
-//     #[inline]
-//     pub const fn min_corner_block_x(self) -> i32 {
-//         match self.detail_level() {
-//             DetailLevel::Block2 => self.center_block_pos_x(),
-//             _ => self.center_block_pos_x() - self.block_width() / 2,
-//         }
-//     }
-
-//     #[inline]
-//     pub const fn min_corner_block_z(self) -> i32 {
-//         match self.detail_level() {
-//             DetailLevel::Block2 => self.center_block_pos_z(),
-//             _ => self.center_block_pos_z() - self.block_width() / 2,
-//         }
-//     }
-
-//     #[inline]
-//     pub const fn block_width(self) -> i32 {
-//         self.detail_level().block_width()
-//     }
-
-//     #[inline]
-//     pub const fn center_block_pos_x(self) -> i32 {
-//         let x = self.x();
-
-//         match self.detail_level() {
-//             DetailLevel::Block => x,
-//             DetailLevel::Block2 => x * 2,
-//             d => (x << d as u8) + (1 << (d as u8 - 1)),
-//         }
-//     }
-
-//     #[inline]
-//     pub const fn center_block_pos_z(self) -> i32 {
-//         let z = self.z();
-
-//         match self.detail_level() {
-//             DetailLevel::Block => z,
-//             DetailLevel::Block2 => z * 2,
-//             d => (z << d as u8) + (1 << (d as u8 - 1)),
-//         }
-//     }
-// }
-
-// impl core::fmt::Display for SectionPosLegazy {
-//     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-//         if f.alternate() {
-//             f.debug_struct("Pos")
-//                 .field("x", &self.x())
-//                 .field("z", &self.z())
-//                 .field("detail_level", &self.detail_level())
-//                 .finish()
-//         } else {
-//             write!(f, "{:?}*{},{}", self.detail_level(), self.x(), self.z())
-//         }
-//     }
-// }
-
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-
-//     #[test]
-//     fn zero() {
-//         let pos = SectionPosLegazy::new(DetailLevel::Block, 0, 0);
-//         assert_eq!(pos.detail_level(), DetailLevel::Block);
-//         assert_eq!(pos.x(), 0);
-//         assert_eq!(pos.z(), 0);
-//         assert_eq!(pos.min_corner_block_x(), 0);
-//         assert_eq!(pos.min_corner_block_z(), 0);
-//         assert_eq!(pos.block_width(), 1);
-//         assert_eq!(pos.center_block_pos_x(), 0);
-//         assert_eq!(pos.center_block_pos_z(), 0);
-//     }
-
-//     #[test]
-//     fn maximum() {
-//         let x = POS_X_MASK >> 1 as i32;
-//         let z = POS_Z_MASK >> 1 as i32;
-
-//         let pos = SectionPosLegazy::new(DetailLevel::Block, x, z);
-//         assert_eq!(pos.x(), x);
-//         assert_eq!(pos.z(), z);
-//     }
-
-//     #[test]
-//     fn negative() {
-//         let x = -2;
-//         let z = -2;
-
-//         let pos = SectionPosLegazy::new(DetailLevel::Block, x, z);
-//         assert_eq!(pos.x(), x);
-//         assert_eq!(pos.z(), z);
-//     }
-
-//     #[test]
-//     #[should_panic]
-//     fn too_small() {
-//         let x = i32::MIN;
-//         let z = i32::MIN;
-
-//         let _pos = SectionPosLegazy::new(DetailLevel::Block, x, z);
-//     }
-
-//     #[test]
-//     #[should_panic]
-//     fn too_large() {
-//         let x = i32::MAX;
-//         let z = i32::MAX;
-
-//         let _pos = SectionPosLegazy::new(DetailLevel::Block, x, z);
-//     }
-// }
+impl core::str::FromStr for Pos {
+    type Err = crate::Error;
+
+    /// Parses the [`Display`](core::fmt::Display) format, `Chunk4*-3,12`.
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use crate::Error;
+
+        let (detail_level, xz) = s
+            .split_once('*')
+            .ok_or_else(|| Error::parse("expected <detail level>*<x>,<z>"))?;
+        let (x, z) = xz
+            .split_once(',')
+            .ok_or_else(|| Error::parse("expected <x>,<z>"))?;
+        let coord = |v: &str, name| {
+            v.trim()
+                .parse()
+                .map_err(|_| Error::parse(format!("invalid {name}: {v:?}")))
+        };
+
+        Ok(Self {
+            detail_level: detail_level.trim().parse()?,
+            x: coord(x, "x")?,
+            z: coord(z, "z")?,
+        })
+    }
+}
+
+const DETAIL_LEVEL_WIDTH: u32 = 8;
+const X_POS_WIDTH: u32 = 28;
+const Z_POS_WIDTH: u32 = 28;
+const X_POS_MISSING_WIDTH: u32 = 32 - X_POS_WIDTH;
+const Z_POS_MISSING_WIDTH: u32 = 32 - Z_POS_WIDTH;
+
+const DETAIL_LEVEL_OFFSET: u32 = 0;
+const POS_X_OFFSET: u32 = DETAIL_LEVEL_OFFSET + DETAIL_LEVEL_WIDTH;
+const POS_Z_OFFSET: u32 = POS_X_OFFSET + X_POS_WIDTH;
+
+const DETAIL_LEVEL_MASK: i64 = u8::MAX as i64;
+const POS_X_MASK: i32 = (1 << X_POS_WIDTH) - 1;
+const POS_Z_MASK: i32 = (1 << Z_POS_WIDTH) - 1;
+
+/// A [`Pos`] packed into 64 bits the way DH's `DhSectionPos` encodes it:
+/// the detail level in the lowest 8 bits, then 28 bits each of x and z.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PackedPos(i64);
+
+impl PackedPos {
+    /// Smallest x and z that fit.
+    pub const MIN_COORDINATE: i32 = -(1 << (X_POS_WIDTH - 1));
+    /// Largest x and z that fit.
+    pub const MAX_COORDINATE: i32 = (1 << (X_POS_WIDTH - 1)) - 1;
+
+    /// # Panics
+    ///
+    /// If `x` or `z` is outside [`Self::MIN_COORDINATE`]`..=`[`Self::MAX_COORDINATE`].
+    #[inline]
+    #[must_use]
+    pub const fn new(detail_level: DetailLevel, x: i32, z: i32) -> Self {
+        match Self::try_new(detail_level, x, z) {
+            Some(pos) => pos,
+            None => panic!("coordinate out of range for a packed position"),
+        }
+    }
+
+    /// `None` if `x` or `z` doesn't fit into 28 bits.
+    #[inline]
+    #[must_use]
+    pub const fn try_new(detail_level: DetailLevel, x: i32, z: i32) -> Option<Self> {
+        const RANGE: core::ops::RangeInclusive<i32> =
+            PackedPos::MIN_COORDINATE..=PackedPos::MAX_COORDINATE;
+        if x < *RANGE.start() || x > *RANGE.end() || z < *RANGE.start() || z > *RANGE.end() {
+            return None;
+        }
+
+        let mut data = 0;
+        data |= detail_level as i64 & DETAIL_LEVEL_MASK;
+        data |= ((x & POS_X_MASK) as i64) << POS_X_OFFSET;
+        data |= ((z & POS_Z_MASK) as i64) << POS_Z_OFFSET;
+
+        Some(Self(data))
+    }
+
+    /// Checks the detail level of a value read from DH, `None` if it is out of range.
+    #[inline]
+    #[must_use]
+    pub const fn from_raw(raw: i64) -> Option<Self> {
+        match DetailLevel::try_new((raw & DETAIL_LEVEL_MASK) as u8) {
+            Ok(_) => Some(Self(raw)),
+            Err(()) => None,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn to_raw(self) -> i64 {
+        self.0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn detail_level(self) -> DetailLevel {
+        let dl = ((self.0 >> DETAIL_LEVEL_OFFSET) & DETAIL_LEVEL_MASK) as u8;
+        // Safety: checked by all constructors
+        unsafe { DetailLevel::new_unchecked(dl) }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn x(self) -> i32 {
+        let x = ((self.0 >> POS_X_OFFSET) & POS_X_MASK as i64) as i32;
+        // Sign extend the 28 bits
+        (x << X_POS_MISSING_WIDTH) >> X_POS_MISSING_WIDTH
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn z(self) -> i32 {
+        let z = ((self.0 >> POS_Z_OFFSET) & POS_Z_MASK as i64) as i32;
+        (z << Z_POS_MISSING_WIDTH) >> Z_POS_MISSING_WIDTH
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn unpack(self) -> Pos {
+        Pos::new(self.detail_level(), self.x(), self.z())
+    }
+}
+
+impl From<PackedPos> for Pos {
+    #[inline]
+    fn from(pos: PackedPos) -> Self {
+        pos.unpack()
+    }
+}
+
+impl TryFrom<Pos> for PackedPos {
+    type Error = crate::Error;
+
+    #[inline]
+    fn try_from(pos: Pos) -> Result<Self, Self::Error> {
+        Self::try_new(pos.detail_level, pos.x, pos.z).ok_or_else(|| {
+            crate::Error::format(format!("{pos} is out of range for a packed position"))
+        })
+    }
+}
+
+impl core::fmt::Display for PackedPos {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(&self.unpack(), f)
+    }
+}
+
+impl crate::backend::ToSql for PackedPos {
+    #[inline]
+    fn to_sql(&self) -> crate::backend::Result<crate::backend::types::ToSqlOutput<'_>> {
+        Ok(self.0.into())
+    }
+}
+
+impl crate::backend::types::FromSql for PackedPos {
+    #[inline]
+    fn column_result(
+        value: crate::backend::types::ValueRef<'_>,
+    ) -> crate::backend::types::FromSqlResult<Self> {
+        Self::from_raw(i64::column_result(value)?)
+            .ok_or(crate::backend::types::FromSqlError::InvalidType)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero() {
+        let pos = PackedPos::new(DetailLevel::Block, 0, 0);
+        assert_eq!(pos.detail_level(), DetailLevel::Block);
+        assert_eq!(pos.x(), 0);
+        assert_eq!(pos.z(), 0);
+
+        let pos = pos.unpack();
+        assert_eq!(pos.min_x(), 0);
+        assert_eq!(pos.min_z(), 0);
+        assert_eq!(pos.detail_level.block_width(), 1);
+        assert_eq!(pos.center_x(), 0);
+        assert_eq!(pos.center_z(), 0);
+    }
+
+    #[test]
+    fn maximum() {
+        let x = POS_X_MASK >> 1;
+        let z = POS_Z_MASK >> 1;
+
+        let pos = PackedPos::new(DetailLevel::Block, x, z);
+        assert_eq!(pos.x(), x);
+        assert_eq!(pos.z(), z);
+    }
+
+    #[test]
+    fn negative() {
+        let x = -2;
+        let z = -2;
+
+        let pos = PackedPos::new(DetailLevel::Block, x, z);
+        assert_eq!(pos.x(), x);
+        assert_eq!(pos.z(), z);
+    }
+
+    #[test]
+    fn round_trip() {
+        let pos = Pos::new(DetailLevel::Region512, PackedPos::MIN_COORDINATE, -7);
+        let packed = PackedPos::try_from(pos).unwrap();
+        assert_eq!(Pos::from(packed), pos);
+        assert_eq!(PackedPos::from_raw(packed.to_raw()), Some(packed));
+    }
+
+    #[test]
+    fn invalid_detail_level() {
+        assert_eq!(PackedPos::from_raw(DetailLevel::MAX as i64 + 1), None);
+    }
+
+    #[test]
+    fn negative_coordinates_round_down() {
+        let chunk = |x, z| Pos::new(DetailLevel::Chunk, x, z);
+        assert_eq!(Pos::from_block(DetailLevel::Chunk, -1, -16), chunk(-1, -1));
+        assert_eq!(Pos::from_block(DetailLevel::Chunk, -17, 15), chunk(-2, 0));
+        assert_eq!(
+            Pos::from_block(DetailLevel::Chunk4, -65, -64),
+            Pos::new(DetailLevel::Chunk4, -2, -1)
+        );
+
+        let region = |x, z| Pos::new(DetailLevel::Region, x, z);
+        assert_eq!(
+            Pos::from_chunk(DetailLevel::Region, -1, -32),
+            region(-1, -1)
+        );
+        assert_eq!(Pos::from_chunk(DetailLevel::Region, -33, 31), region(-2, 0));
+        assert_eq!(
+            Pos::from_region(DetailLevel::Region4, -1, -5),
+            Pos::new(DetailLevel::Region4, -1, -2)
+        );
+
+        // Finer levels get the minimum corner
+        assert_eq!(
+            Pos::from_chunk(DetailLevel::Block, -1, 2),
+            Pos::new(DetailLevel::Block, -16, 32)
+        );
+        assert_eq!(
+            Pos::from_region(DetailLevel::Chunk4, -1, 0),
+            Pos::new(DetailLevel::Chunk4, -8, 0)
+        );
+
+        let pos = Pos::new(DetailLevel::Chunk4, -3, 5);
+        let coarse = pos.to_detail_level(DetailLevel::Chunk16);
+        assert_eq!(coarse, Pos::new(DetailLevel::Chunk16, -1, 1));
+        assert_eq!(
+            coarse.to_detail_level(DetailLevel::Chunk4),
+            Pos::new(DetailLevel::Chunk4, -4, 4)
+        );
+        assert_eq!(pos.to_detail_level(DetailLevel::Chunk4), pos);
+    }
+
+    #[test]
+    fn parents_and_children() {
+        for (x, z) in [(0, 0), (-1, -1), (5, -6), (-7, 2)] {
+            let pos = Pos::new(DetailLevel::Chunk16, x, z);
+            let children = pos.children().unwrap();
+            for (i, child) in children.into_iter().enumerate() {
+                assert_eq!(child.detail_level, DetailLevel::Chunk8);
+                assert_eq!(child.parent(), Some(pos));
+                assert!(pos.contains(child));
+                // The child index DH and the section tree use
+                assert_eq!(((child.x & 1) + 2 * (child.z & 1)) as usize, i);
+            }
+            assert_eq!(children[0], pos.to_detail_level(DetailLevel::Chunk8));
+            assert_eq!(children[3].x, children[0].x + 1);
+            assert_eq!(children[3].z, children[0].z + 1);
+        }
+
+        assert_eq!(Pos::new(DetailLevel::Block, 1, 1).children(), None);
+        assert_eq!(Pos::new(DetailLevel::MAX, 1, 1).parent(), None);
+        assert_eq!(
+            Pos::new(DetailLevel::Block, -1, 2).parent(),
+            Some(Pos::new(DetailLevel::Block2, -1, 1))
+        );
+    }
+
+    #[test]
+    fn neighbours() {
+        let pos = Pos::new(DetailLevel::Chunk4, 0, -1);
+        let chunk4 = |x, z| Pos::new(DetailLevel::Chunk4, x, z);
+        assert_eq!(
+            pos.neighbours(),
+            [chunk4(0, -2), chunk4(0, 0), chunk4(-1, -1), chunk4(1, -1)]
+        );
+        for neighbour in pos.neighbours() {
+            assert!(neighbour.neighbours().contains(&pos));
+            assert!(!pos.contains(neighbour));
+        }
+    }
+
+    #[test]
+    fn contains() {
+        // Blocks -64..=-1 and 128..=191
+        let pos = Pos::new(DetailLevel::Chunk4, -1, 2);
+        for (x, z) in [(-64, 128), (-1, 191), (-64, 191), (-1, 128)] {
+            assert!(pos.contains_block(x, z), "{x},{z}");
+        }
+        for (x, z) in [(0, 128), (-65, 128), (-1, 192), (-1, 127)] {
+            assert!(!pos.contains_block(x, z), "{x},{z}");
+        }
+
+        assert!(pos.contains(pos));
+        assert!(pos.contains(Pos::from_block(DetailLevel::Block, -64, 191)));
+        assert!(!pos.contains(Pos::from_block(DetailLevel::Block, -65, 191)));
+        assert!(!pos.contains(pos.parent().unwrap()));
+        assert!(!pos.contains(Pos::new(DetailLevel::Chunk8, -1, 1)));
+    }
+
+    #[test]
+    fn parse() {
+        let pos = Pos::new(DetailLevel::Chunk4, -3, 12);
+        assert_eq!("Chunk4*-3,12".parse::<Pos>().unwrap(), pos);
+        assert_eq!(" chunk4 * -3 , 12 ".parse::<Pos>().unwrap(), pos);
+        assert_eq!("6*-3,12".parse::<Pos>().unwrap(), pos);
+        assert_eq!(pos.to_string().parse::<Pos>().unwrap(), pos);
+
+        for invalid in [
+            "",
+            "Chunk4",
+            "Chunk4*-3",
+            "Chunk4*x,12",
+            "Chunk5*1,1",
+            "-3,12",
+        ] {
+            assert!(invalid.parse::<Pos>().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_small() {
+        let x = i32::MIN;
+        let z = i32::MIN;
+
+        let _pos = PackedPos::new(DetailLevel::Block, x, z);
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_large() {
+        let x = i32::MAX;
+        let z = i32::MAX;
+
+        let _pos = PackedPos::new(DetailLevel::Block, x, z);
+    }
+}
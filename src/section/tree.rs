@@ -0,0 +1,413 @@
+//! Which sections exist, across all detail levels.
+//!
+//! Like DH's LOD quadtree, each node of the coarsest level has four children one level finer.
+//! Nodes only exist on the way to a stored section, a missing node means no data at all.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    DetailLevel, Result,
+    backend::{self, Connection, Row},
+    repo::{All, Repo},
+};
+
+use super::pos::Pos;
+
+#[derive(Debug, Default, Clone)]
+struct Node {
+    /// Whether there is a section at this position
+    present: bool,
+    /// Indexed like [`Pos::children`]
+    children: [Option<Box<Node>>; 4],
+}
+
+impl Node {
+    #[inline]
+    fn child(&self, pos: Pos) -> Option<&Self> {
+        self.children[child_index(pos)].as_deref()
+    }
+
+    fn for_each(&self, pos: Pos, f: &mut impl FnMut(Pos)) {
+        if self.present {
+            f(pos);
+        }
+        if let Some(children) = pos.children() {
+            for (node, child) in self.children.iter().zip(children) {
+                if let Some(node) = node {
+                    node.for_each(child, f);
+                }
+            }
+        }
+    }
+
+    /// Positions at `detail_level` with a node, this one being at `pos`.
+    fn for_each_at(&self, pos: Pos, detail_level: DetailLevel, f: &mut impl FnMut(Pos)) {
+        if pos.detail_level == detail_level {
+            return f(pos);
+        }
+        if let Some(children) = pos.children() {
+            for (node, child) in self.children.iter().zip(children) {
+                if let Some(node) = node {
+                    node.for_each_at(child, detail_level, f);
+                }
+            }
+        }
+    }
+}
+
+/// Index of `pos` among the children of its parent.
+#[inline]
+const fn child_index(pos: Pos) -> usize {
+    ((pos.x & 1) + 2 * (pos.z & 1)) as usize
+}
+
+/// Positions of the sections in the database, only the keys are selected.
+struct Positions;
+
+impl Repo for Positions {
+    const SELECTION: &'static str = "DetailLevel, PosX, PosZ";
+    const TABLE: &'static str = "FullData";
+
+    type Element<'r> = Pos;
+
+    #[inline]
+    fn from_row<'r>(row: &'r Row) -> backend::Result<Self::Element<'r>> {
+        let detail_level: DetailLevel = row.get("DetailLevel")?;
+        Ok(Pos::new(
+            detail_level + Pos::SECTION_MINIMUM_DETAIL_LEVEL,
+            row.get("PosX")?,
+            row.get("PosZ")?,
+        ))
+    }
+}
+
+/// Quadtree of the available sections.
+#[derive(Debug, Default, Clone)]
+pub struct SectionTree {
+    /// Nodes at [`Self::ROOT_DETAIL_LEVEL`]
+    roots: BTreeMap<(i32, i32), Node>,
+    len: usize,
+}
+
+impl SectionTree {
+    pub const ROOT_DETAIL_LEVEL: DetailLevel = DetailLevel::MAX;
+
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the positions of all sections, without their data.
+    #[inline]
+    pub fn load(conn: &Connection) -> Result<Self> {
+        let mut tree = Self::new();
+        Positions::select_each_with(conn, &All, (), |pos| {
+            tree.insert(pos);
+            Ok::<_, crate::Error>(())
+        })?;
+        Ok(tree)
+    }
+
+    /// Number of sections.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds a section, returns whether it was new.
+    #[inline]
+    pub fn insert(&mut self, pos: Pos) -> bool {
+        let root = pos.to_detail_level(Self::ROOT_DETAIL_LEVEL);
+        let mut node = self.roots.entry((root.x, root.z)).or_default();
+        for level in (pos.detail_level as u8..Self::ROOT_DETAIL_LEVEL as u8).rev() {
+            // Safety: below the root level
+            let step = pos.to_detail_level(unsafe { DetailLevel::new_unchecked(level) });
+            node = node.children[child_index(step)].get_or_insert_default();
+        }
+
+        let new = !node.present;
+        node.present = true;
+        self.len += usize::from(new);
+        new
+    }
+
+    /// The node at `pos`, `None` if there is no section at or inside it.
+    fn node(&self, pos: Pos) -> Option<&Node> {
+        let root = pos.to_detail_level(Self::ROOT_DETAIL_LEVEL);
+        let mut node = self.roots.get(&(root.x, root.z))?;
+        for level in (pos.detail_level as u8..Self::ROOT_DETAIL_LEVEL as u8).rev() {
+            // Safety: below the root level
+            node = node.child(pos.to_detail_level(unsafe { DetailLevel::new_unchecked(level) }))?;
+        }
+        Some(node)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, pos: Pos) -> bool {
+        self.node(pos).is_some_and(|n| n.present)
+    }
+
+    /// Whether there is a section at `pos` or at any finer level inside it.
+    #[inline]
+    #[must_use]
+    pub fn has_data(&self, pos: Pos) -> bool {
+        self.node(pos).is_some()
+    }
+
+    /// The finest section containing the block.
+    #[inline]
+    #[must_use]
+    pub fn finest_at(&self, x: i32, z: i32) -> Option<Pos> {
+        let root = Pos::from_block(Self::ROOT_DETAIL_LEVEL, x, z);
+        let mut node = self.roots.get(&(root.x, root.z))?;
+        let mut finest = node.present.then_some(root);
+        for level in (0..Self::ROOT_DETAIL_LEVEL as u8).rev() {
+            // Safety: below the root level
+            let pos = Pos::from_block(unsafe { DetailLevel::new_unchecked(level) }, x, z);
+            let Some(child) = node.child(pos) else {
+                break;
+            };
+            node = child;
+            if node.present {
+                finest = Some(pos);
+            }
+        }
+        finest
+    }
+
+    /// All sections, coarsest root first and depth first inside it.
+    #[inline]
+    #[must_use]
+    pub fn positions(&self) -> Vec<Pos> {
+        let mut positions = Vec::with_capacity(self.len);
+        for (&(x, z), node) in &self.roots {
+            node.for_each(Pos::new(Self::ROOT_DETAIL_LEVEL, x, z), &mut |p| {
+                positions.push(p);
+            });
+        }
+        positions
+    }
+
+    /// Whether a section at any detail level overlaps `pos`.
+    #[inline]
+    #[must_use]
+    pub fn covers(&self, pos: Pos) -> bool {
+        let root = pos.to_detail_level(Self::ROOT_DETAIL_LEVEL);
+        let Some(mut node) = self.roots.get(&(root.x, root.z)) else {
+            return false;
+        };
+        for level in (pos.detail_level as u8..Self::ROOT_DETAIL_LEVEL as u8).rev() {
+            if node.present {
+                return true;
+            }
+            // Safety: below the root level
+            match node.child(pos.to_detail_level(unsafe { DetailLevel::new_unchecked(level) })) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// Positions at `detail_level` no section of any level overlaps,
+    /// within the bounding box of those at or inside `detail_level` with data.
+    #[inline]
+    #[must_use]
+    pub fn gaps(&self, detail_level: DetailLevel) -> Vec<Pos> {
+        let mut min = (i32::MAX, i32::MAX);
+        let mut max = (i32::MIN, i32::MIN);
+        for (&(x, z), node) in &self.roots {
+            node.for_each_at(
+                Pos::new(Self::ROOT_DETAIL_LEVEL, x, z),
+                detail_level,
+                &mut |p| {
+                    min = (min.0.min(p.x), min.1.min(p.z));
+                    max = (max.0.max(p.x), max.1.max(p.z));
+                },
+            );
+        }
+
+        let mut gaps = Vec::new();
+        for z in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                let pos = Pos::new(detail_level, x, z);
+                if !self.covers(pos) {
+                    gaps.push(pos);
+                }
+            }
+        }
+        gaps
+    }
+
+    /// Parents of `children` that have no section, sorted and without duplicates.
+    #[inline]
+    #[must_use]
+    pub fn missing_parents(&self, children: impl IntoIterator<Item = Pos>) -> Vec<Pos> {
+        let mut missing: Vec<_> = children
+            .into_iter()
+            .filter_map(Pos::parent)
+            .filter(|parent| !self.contains(*parent))
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+        missing
+    }
+}
+
+impl FromIterator<Pos> for SectionTree {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = Pos>>(iter: I) -> Self {
+        let mut tree = Self::new();
+        for pos in iter {
+            tree.insert(pos);
+        }
+        tree
+    }
+}
+
+impl Extend<Pos> for SectionTree {
+    #[inline]
+    fn extend<I: IntoIterator<Item = Pos>>(&mut self, iter: I) {
+        for pos in iter {
+            self.insert(pos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn chunk4(x: i32, z: i32) -> Pos {
+        Pos::new(DetailLevel::Chunk4, x, z)
+    }
+
+    const fn chunk16(x: i32, z: i32) -> Pos {
+        Pos::new(DetailLevel::Chunk16, x, z)
+    }
+
+    #[test]
+    fn child_indices() {
+        for pos in [chunk16(0, 0), chunk16(-1, -1), chunk16(3, -2)] {
+            for (i, child) in pos.children().unwrap().into_iter().enumerate() {
+                assert_eq!(child_index(child), i);
+            }
+        }
+    }
+
+    #[test]
+    fn insert_contains_has_data() {
+        let mut tree = SectionTree::new();
+        assert!(tree.is_empty());
+        assert!(tree.insert(chunk4(-1, -1)));
+        assert!(tree.insert(chunk4(2, 0)));
+        assert!(!tree.insert(chunk4(-1, -1)));
+        assert_eq!(tree.len(), 2);
+
+        assert!(tree.contains(chunk4(-1, -1)));
+        assert!(!tree.contains(chunk4(0, -1)));
+        assert!(!tree.contains(chunk4(1, 1)));
+
+        // Ancestors have data without being sections themselves
+        let chunk8 = Pos::new(DetailLevel::Chunk8, -1, -1);
+        assert!(!tree.contains(chunk8));
+        assert!(tree.has_data(chunk8));
+        assert!(tree.has_data(chunk16(-1, -1)));
+        assert!(tree.has_data(Pos::new(DetailLevel::MAX, -1, -1)));
+        assert!(!tree.has_data(chunk4(-2, -1)));
+        assert!(!tree.has_data(Pos::new(DetailLevel::Chunk8, 0, -1)));
+
+        assert_eq!(tree.positions(), [chunk4(-1, -1), chunk4(2, 0)]);
+    }
+
+    #[test]
+    fn finest_at() {
+        let tree: SectionTree = [chunk16(-1, -1), chunk4(-1, -1)].into_iter().collect();
+
+        assert_eq!(tree.finest_at(-1, -1), Some(chunk4(-1, -1)));
+        assert_eq!(tree.finest_at(-64, -64), Some(chunk4(-1, -1)));
+        // Next to the Chunk4 section, inside the Chunk16 one
+        assert_eq!(tree.finest_at(-65, -1), Some(chunk16(-1, -1)));
+        assert_eq!(tree.finest_at(-256, -256), Some(chunk16(-1, -1)));
+        assert_eq!(tree.finest_at(-257, -1), None);
+        assert_eq!(tree.finest_at(0, 0), None);
+    }
+
+    #[test]
+    fn covers() {
+        let tree: SectionTree = [chunk16(-1, -1)].into_iter().collect();
+
+        // Inside the section
+        assert!(tree.covers(chunk4(-4, -4)));
+        assert!(tree.covers(chunk4(-1, -1)));
+        assert!(tree.covers(chunk16(-1, -1)));
+        // Around it
+        assert!(tree.covers(Pos::new(DetailLevel::Region, -1, -1)));
+        assert!(!tree.covers(Pos::new(DetailLevel::Region, 0, -1)));
+        // Next to it
+        assert!(!tree.covers(chunk4(0, -1)));
+        assert!(!tree.covers(chunk4(-5, -1)));
+    }
+
+    #[test]
+    fn gaps() {
+        let mut tree: SectionTree = [chunk4(-2, -1), chunk4(0, -1), chunk4(0, 1)]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            tree.gaps(DetailLevel::Chunk4),
+            [
+                chunk4(-1, -1),
+                chunk4(-2, 0),
+                chunk4(-1, 0),
+                chunk4(0, 0),
+                chunk4(-2, 1),
+                chunk4(-1, 1)
+            ]
+        );
+
+        // A coarser section fills the gaps it overlaps
+        tree.insert(Pos::new(DetailLevel::Chunk8, -1, -1));
+        tree.insert(Pos::new(DetailLevel::Chunk8, -1, 0));
+        assert_eq!(tree.gaps(DetailLevel::Chunk4), [chunk4(0, 0)]);
+
+        assert!(SectionTree::new().gaps(DetailLevel::Chunk4).is_empty());
+    }
+
+    #[test]
+    fn missing_parents() {
+        let tree: SectionTree = [
+            chunk4(-1, -1),
+            chunk4(-2, -2),
+            Pos::new(DetailLevel::Chunk8, 0, 0),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            tree.missing_parents([
+                chunk4(-1, -1),
+                chunk4(-2, -2),
+                chunk4(0, 1),
+                chunk4(1, 1),
+                chunk4(3, 0)
+            ]),
+            [
+                Pos::new(DetailLevel::Chunk8, -1, -1),
+                Pos::new(DetailLevel::Chunk8, 1, 0)
+            ]
+        );
+        assert!(
+            tree.missing_parents([Pos::new(DetailLevel::MAX, -3, 2)])
+                .is_empty()
+        );
+    }
+}
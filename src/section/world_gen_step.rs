@@ -1,5 +1,5 @@
 /// https://gitlab.com/distant-horizons-team/distant-horizons-core/-/blob/main/api/src/main/java/com/seibel/distanthorizons/api/enums/worldGeneration/EDhApiWorldGenerationStep.java
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(u8)]
 pub enum WorldGenStep {
     DownSampled = u8::MAX.wrapping_sub(1),
@@ -16,13 +16,15 @@ pub enum WorldGenStep {
 }
 
 impl TryFrom<u8> for WorldGenStep {
-    type Error = anyhow::Error;
+    type Error = crate::Error;
 
     #[inline]
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
             v @ (0..=9 | 254) => Ok(unsafe { std::mem::transmute::<u8, Self>(v) }),
-            _ => anyhow::bail!("invalid value: {value}"),
+            _ => Err(crate::Error::format(format!(
+                "invalid world generation step: {value}"
+            ))),
         }
     }
 }
@@ -47,20 +49,29 @@ impl AsRef<str> for WorldGenStep {
 }
 
 impl TryFrom<Box<[u8]>> for super::columns::Columns<WorldGenStep> {
-    type Error = anyhow::Error;
+    type Error = crate::Error;
 
     #[inline]
     fn try_from(data: Box<[u8]>) -> Result<Self, Self::Error> {
-        use anyhow::Context;
-
         let mut data_iter = data.into_iter();
         let mut cols = [WorldGenStep::Empty; Self::LEN];
         cols.iter_mut().try_for_each(|col| {
-            let next = data_iter.next().context("not enough values")?;
+            let next = data_iter
+                .next()
+                .ok_or_else(|| crate::Error::format("not enough values"))?;
             *col = next.try_into()?;
-            anyhow::Ok(())
+            Ok::<_, crate::Error>(())
         })?;
-        // anyhow::ensure!(data_iter.next().is_none(), "too many values");
+        // ensure!(data_iter.next().is_none(), "too many values");
         Ok(Self::new(cols))
     }
 }
+
+impl TryFrom<&super::columns::Columns<WorldGenStep>> for Box<[u8]> {
+    type Error = crate::Error;
+
+    #[inline]
+    fn try_from(cols: &super::columns::Columns<WorldGenStep>) -> Result<Self, Self::Error> {
+        Ok(cols.as_ref().iter().map(|&col| col as u8).collect())
+    }
+}
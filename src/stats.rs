@@ -0,0 +1,317 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    Compression, DetailLevel, Result, Section, beacon::BeaconBeam, schema::Schema,
+    section::pos::Pos, section::world_gen_step::WorldGenStep,
+};
+
+/// Block area covered by sections, both corners inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Area {
+    pub min_x: i32,
+    pub min_z: i32,
+    pub max_x: i32,
+    pub max_z: i32,
+}
+
+impl Area {
+    #[inline]
+    #[must_use]
+    pub const fn of(pos: Pos) -> Self {
+        let width = pos.detail_level.block_width();
+        Self {
+            min_x: pos.min_x(),
+            min_z: pos.min_z(),
+            max_x: pos.min_x() + width - 1,
+            max_z: pos.min_z() + width - 1,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            min_x: self.min_x.min(other.min_x),
+            min_z: self.min_z.min(other.min_z),
+            max_x: self.max_x.max(other.max_x),
+            max_z: self.max_z.max(other.max_z),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn width(&self) -> i64 {
+        self.max_x as i64 - self.min_x as i64 + 1
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn depth(&self) -> i64 {
+        self.max_z as i64 - self.min_z as i64 + 1
+    }
+}
+
+/// Summary of the sections of one detail level.
+#[derive(Debug, Clone, Default)]
+pub struct LevelStats {
+    pub sections: usize,
+    /// Sections that failed to decompress, they only count towards the metadata
+    pub failed: usize,
+    pub area: Option<Area>,
+    pub compression: BTreeMap<Compression, usize>,
+    pub format_versions: BTreeMap<u8, usize>,
+    /// Number of columns per generation step
+    pub world_gen_steps: BTreeMap<WorldGenStep, usize>,
+    /// Bytes stored in the database
+    pub compressed_bytes: usize,
+    /// Approximate bytes of the decompressed data in memory
+    pub decompressed_bytes: usize,
+    pub oldest: Option<i64>,
+    pub newest: Option<i64>,
+}
+
+impl LevelStats {
+    /// Adds a section, decompressing it for the column level statistics.
+    #[inline]
+    pub fn add(&mut self, section: &mut Section) {
+        let area = Area::of(section.pos);
+        let modified = section.last_modified();
+
+        self.sections += 1;
+        self.area = Some(self.area.map_or(area, |a| a.union(area)));
+        *self.compression.entry(section.compression()).or_default() += 1;
+        *self
+            .format_versions
+            .entry(section.format_version())
+            .or_default() += 1;
+        self.compressed_bytes += section.compressed_size();
+        self.oldest = Some(self.oldest.map_or(modified, |o| o.min(modified)));
+        self.newest = Some(self.newest.map_or(modified, |n| n.max(modified)));
+
+        if section.decompress().is_err() {
+            self.failed += 1;
+            return;
+        }
+        self.decompressed_bytes += section.decompressed_size();
+        if let Some(steps) = section.world_gen_step() {
+            for step in steps.as_ref() {
+                *self.world_gen_steps.entry(*step).or_default() += 1;
+            }
+        }
+    }
+
+    #[inline]
+    pub fn merge(&mut self, other: &Self) {
+        fn merge_counts<K: Ord + Copy>(into: &mut BTreeMap<K, usize>, from: &BTreeMap<K, usize>) {
+            for (k, v) in from {
+                *into.entry(*k).or_default() += v;
+            }
+        }
+
+        self.sections += other.sections;
+        self.failed += other.failed;
+        self.area = match (self.area, other.area) {
+            (Some(a), Some(b)) => Some(a.union(b)),
+            (a, b) => a.or(b),
+        };
+        merge_counts(&mut self.compression, &other.compression);
+        merge_counts(&mut self.format_versions, &other.format_versions);
+        merge_counts(&mut self.world_gen_steps, &other.world_gen_steps);
+        self.compressed_bytes += other.compressed_bytes;
+        self.decompressed_bytes += other.decompressed_bytes;
+        self.oldest = self.oldest.into_iter().chain(other.oldest).min();
+        self.newest = self.newest.into_iter().chain(other.newest).max();
+    }
+}
+
+/// Report over a whole DH database.
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub levels: BTreeMap<DetailLevel, LevelStats>,
+    pub beacons: usize,
+}
+
+impl Stats {
+    /// Reads every section, or only those of `detail_level`, one at a time.
+    pub fn collect(
+        conn: &crate::backend::Connection,
+        detail_level: Option<DetailLevel>,
+    ) -> Result<Self> {
+        let mut stats = Self::default();
+
+        Section::for_each(conn, detail_level, |mut section| {
+            stats
+                .levels
+                .entry(section.pos.detail_level)
+                .or_default()
+                .add(&mut section);
+            Ok::<_, crate::Error>(())
+        })?;
+
+        // Databases written before DH tracked beacons have no BeaconBeam table
+        if Schema::detect(conn)?.beacons {
+            stats.beacons = BeaconBeam::get_all(conn)?.len();
+        }
+
+        Ok(stats)
+    }
+
+    /// All detail levels combined.
+    #[inline]
+    #[must_use]
+    pub fn total(&self) -> LevelStats {
+        let mut total = LevelStats::default();
+        for level in self.levels.values() {
+            total.merge(level);
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::section::{
+        builder::{Run, SectionBuilder},
+        mapping::Entry,
+    };
+
+    /// Stone in the first `columns` columns, compressed like a section read from the database.
+    fn section(
+        pos: Pos,
+        columns: usize,
+        compression: Compression,
+        modified: i64,
+    ) -> Section<'static> {
+        let mut builder = SectionBuilder::new(pos)
+            .with_compression(compression)
+            .with_timestamps(0, modified);
+        for i in 0..columns {
+            let run = Run::new(Entry::new("minecraft:plains", "minecraft:stone"), -64..0);
+            builder
+                .push(i / Section::WIDTH, i % Section::WIDTH, run)
+                .unwrap();
+        }
+        let mut section = builder.build().unwrap();
+        section.compress().unwrap();
+        section
+    }
+
+    #[test]
+    fn area() {
+        let a = Area::of(Pos::new(DetailLevel::Chunk4, -1, 0));
+        assert_eq!(
+            a,
+            Area {
+                min_x: -64,
+                min_z: 0,
+                max_x: -1,
+                max_z: 63
+            }
+        );
+        let b = Area::of(Pos::new(DetailLevel::Chunk4, 1, -2));
+        let union = a.union(b);
+        assert_eq!(
+            union,
+            Area {
+                min_x: -64,
+                min_z: -128,
+                max_x: 127,
+                max_z: 63
+            }
+        );
+        assert_eq!(union, b.union(a));
+        assert_eq!((union.width(), union.depth()), (192, 192));
+    }
+
+    #[test]
+    fn add() {
+        let mut stats = LevelStats::default();
+        let mut a = section(
+            Pos::new(DetailLevel::Chunk4, -1, 0),
+            10,
+            Compression::Lzma2,
+            300,
+        );
+        let mut b = section(
+            Pos::new(DetailLevel::Chunk4, 2, 1),
+            64 * 64,
+            Compression::Uncompressed,
+            100,
+        );
+        let compressed = a.compressed_size() + b.compressed_size();
+        stats.add(&mut a);
+        stats.add(&mut b);
+
+        assert_eq!(stats.sections, 2);
+        assert_eq!(stats.failed, 0);
+        assert_eq!(
+            stats.area,
+            Some(Area {
+                min_x: -64,
+                min_z: 0,
+                max_x: 191,
+                max_z: 127
+            })
+        );
+        assert_eq!(
+            stats.compression,
+            BTreeMap::from([(Compression::Uncompressed, 1), (Compression::Lzma2, 1)])
+        );
+        assert_eq!(stats.format_versions, BTreeMap::from([(1, 2)]));
+        assert_eq!(
+            stats.world_gen_steps,
+            BTreeMap::from([
+                (WorldGenStep::Empty, 64 * 64 - 10),
+                (WorldGenStep::Light, 64 * 64 + 10)
+            ])
+        );
+        assert_eq!(stats.compressed_bytes, compressed);
+        assert!(stats.decompressed_bytes > 0);
+        assert_eq!((stats.oldest, stats.newest), (Some(100), Some(300)));
+    }
+
+    #[test]
+    fn merge() {
+        let mut sections = [
+            section(
+                Pos::new(DetailLevel::Chunk4, 0, 0),
+                1,
+                Compression::Lzma2,
+                50,
+            ),
+            section(
+                Pos::new(DetailLevel::Chunk4, -3, 4),
+                2,
+                Compression::Uncompressed,
+                20,
+            ),
+            section(
+                Pos::new(DetailLevel::Chunk4, 5, -1),
+                3,
+                Compression::Lzma2,
+                70,
+            ),
+        ];
+        let mut all = LevelStats::default();
+        let mut first = LevelStats::default();
+        let mut rest = LevelStats::default();
+        for (i, section) in sections.iter_mut().enumerate() {
+            all.add(section);
+            if i == 0 {
+                first.add(section);
+            } else {
+                rest.add(section);
+            }
+        }
+
+        let mut merged = LevelStats::default();
+        merged.merge(&first);
+        merged.merge(&rest);
+        assert_eq!(format!("{merged:?}"), format!("{all:?}"));
+
+        // Merging nothing changes nothing
+        merged.merge(&LevelStats::default());
+        assert_eq!(format!("{merged:?}"), format!("{all:?}"));
+    }
+}
@@ -0,0 +1,270 @@
+use std::path::Path;
+
+use crate::{Error, Result};
+
+/// A named map location, imported from a minimap mod or saved as a bookmark.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Waypoint {
+    pub name: String,
+    pub x: i32,
+    /// Some mods allow waypoints without a known height
+    pub y: Option<i32>,
+    pub z: i32,
+    pub color: (u8, u8, u8),
+}
+
+/// Colors of the chat formatting codes, used by Xaero's minimap to color waypoints.
+///
+/// <https://minecraft.wiki/w/Formatting_codes#Color_codes>
+const FORMATTING_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (0, 0, 170),
+    (0, 170, 0),
+    (0, 170, 170),
+    (170, 0, 0),
+    (170, 0, 170),
+    (255, 170, 0),
+    (170, 170, 170),
+    (85, 85, 85),
+    (85, 85, 255),
+    (85, 255, 85),
+    (85, 255, 255),
+    (255, 85, 85),
+    (255, 85, 255),
+    (255, 255, 85),
+    (255, 255, 255),
+];
+
+/// White in [`FORMATTING_COLORS`]
+const DEFAULT_COLOR_INDEX: usize = 15;
+
+/// Xaero's minimap escapes colons in names
+const XAERO_COLON: &str = "§§";
+
+/// Loads waypoints from a file, the format is picked by extension:
+/// `.txt` for Xaero's minimap and `.json` for JourneyMap.
+#[inline]
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Waypoint>> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        std::io::Error::new(
+            e.kind(),
+            format!("reading waypoints from {}: {e}", path.display()),
+        )
+    })?;
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("txt") => parse_xaero(&content),
+        #[cfg(feature = "json")]
+        Some("json") => parse_journeymap(&content),
+        _ => Err(Error::format(format!(
+            "unsupported waypoint file: {}",
+            path.display()
+        ))),
+    }
+}
+
+/// Parses a Xaero's minimap waypoint file, skipping disabled waypoints.
+///
+/// `waypoint:name:initials:x:y:z:color:disabled:type:set:rotate_on_tp:tp_yaw:visibility_type:destination`
+#[inline]
+pub fn parse_xaero(content: &str) -> Result<Vec<Waypoint>> {
+    let mut waypoints = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let Some(fields) = line.strip_prefix("waypoint:") else {
+            continue;
+        };
+        let fields: Vec<&str> = fields.split(':').collect();
+        if fields.len() < 7 {
+            return Err(Error::format(format!("line {}: not enough fields", i + 1)));
+        }
+
+        if fields[6] == "true" {
+            continue;
+        }
+
+        let coord = |n: usize| -> Result<i32> {
+            fields[n].parse().map_err(|_| {
+                Error::format(format!(
+                    "line {}: invalid coordinate {:?}",
+                    i + 1,
+                    fields[n]
+                ))
+            })
+        };
+        let color = fields[5]
+            .parse::<usize>()
+            .ok()
+            .and_then(|c| FORMATTING_COLORS.get(c))
+            .copied()
+            .unwrap_or(FORMATTING_COLORS[DEFAULT_COLOR_INDEX]);
+
+        waypoints.push(Waypoint {
+            name: fields[0].replace(XAERO_COLON, ":"),
+            x: coord(2)?,
+            y: coord(3).ok(),
+            z: coord(4)?,
+            color,
+        });
+    }
+
+    Ok(waypoints)
+}
+
+/// Writes waypoints in the format read by [`parse_xaero`].
+#[inline]
+pub fn to_xaero(waypoints: &[Waypoint]) -> String {
+    use core::fmt::Write;
+
+    let mut out = String::from(
+        "#\n#waypoint:name:initials:x:y:z:color:disabled:type:set:rotate_on_tp:tp_yaw:visibility_type:destination\n#\n",
+    );
+    for waypoint in waypoints {
+        let name = waypoint.name.replace(':', XAERO_COLON);
+        let initials: String = name.chars().take(1).collect();
+        let y = waypoint
+            .y
+            .map_or_else(|| "~".to_string(), |y| y.to_string());
+        let color = FORMATTING_COLORS
+            .iter()
+            .position(|c| *c == waypoint.color)
+            .unwrap_or(DEFAULT_COLOR_INDEX);
+        let _ = writeln!(
+            out,
+            "waypoint:{name}:{initials}:{}:{y}:{}:{color}:false:0:gui.xaero_default:false:0:0:false",
+            waypoint.x, waypoint.z,
+        );
+    }
+    out
+}
+
+/// Parses JourneyMap waypoints, either a single waypoint object,
+/// an array of them or an object containing them under `waypoints`.
+#[cfg(feature = "json")]
+#[inline]
+pub fn parse_journeymap(content: &str) -> Result<Vec<Waypoint>> {
+    use serde_json::Value;
+
+    fn waypoint(value: &Value) -> Result<Option<Waypoint>> {
+        if value.get("enable").and_then(Value::as_bool) == Some(false) {
+            return Ok(None);
+        }
+
+        let coord =
+            |key: &str| -> Option<i32> { value.get(key)?.as_f64().map(|v| v.floor() as i32) };
+        let channel = |key: &str| -> Option<u8> { value.get(key)?.as_u64()?.try_into().ok() };
+
+        let color = match (channel("r"), channel("g"), channel("b")) {
+            (Some(r), Some(g), Some(b)) => (r, g, b),
+            _ => match value.get("color").and_then(Value::as_u64) {
+                Some(rgb) => ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8),
+                None => FORMATTING_COLORS[DEFAULT_COLOR_INDEX],
+            },
+        };
+
+        Ok(Some(Waypoint {
+            name: value
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            x: coord("x").ok_or_else(|| Error::format("waypoint without x coordinate"))?,
+            y: coord("y"),
+            z: coord("z").ok_or_else(|| Error::format("waypoint without z coordinate"))?,
+            color,
+        }))
+    }
+
+    let value: Value = serde_json::from_str(content)
+        .map_err(|e| Error::format(format!("parsing JourneyMap waypoints: {e}")))?;
+
+    let list: Vec<&Value> = match value.get("waypoints").unwrap_or(&value) {
+        Value::Array(list) => list.iter().collect(),
+        Value::Object(map) if !map.contains_key("x") => map.values().collect(),
+        single => vec![single],
+    };
+
+    list.into_iter()
+        .filter_map(|v| waypoint(v).transpose())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xaero_round_trip() {
+        let waypoints = vec![
+            Waypoint {
+                name: "Base: north gate".to_string(),
+                x: -120,
+                y: Some(64),
+                z: 3500,
+                color: FORMATTING_COLORS[12],
+            },
+            Waypoint {
+                name: "Portal".to_string(),
+                x: 0,
+                y: None,
+                z: -1,
+                color: FORMATTING_COLORS[DEFAULT_COLOR_INDEX],
+            },
+        ];
+
+        let text = to_xaero(&waypoints);
+        assert!(text.contains("waypoint:Base§§ north gate:B:-120:64:3500:12:false:"));
+        assert_eq!(parse_xaero(&text).unwrap(), waypoints);
+    }
+
+    #[test]
+    fn xaero_skips_disabled_and_comments() {
+        let text = "#\n\
+            waypoint:Home:H:10:70:20:2:false:0:gui.xaero_default:false:0:0:false\n\
+            waypoint:Old:O:1:2:3:4:true:0:gui.xaero_default:false:0:0:false\n";
+        let waypoints = parse_xaero(text).unwrap();
+        assert_eq!(waypoints.len(), 1);
+        assert_eq!(waypoints[0].name, "Home");
+        assert_eq!(waypoints[0].color, (0, 170, 0));
+
+        assert!(parse_xaero("waypoint:Short:S:1:2").is_err());
+        assert!(parse_xaero("waypoint:Bad:B:x:2:3:4:false").is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn journeymap() {
+        let json = r#"{
+            "id": "Village_-312,70,1024",
+            "name": "Village",
+            "icon": "journeymap:ui/img/waypoint-icon.png",
+            "x": -312, "y": 70, "z": 1024.7,
+            "r": 255, "g": 85, "b": 0,
+            "enable": true,
+            "type": "Normal",
+            "origin": "journeymap",
+            "dimensions": ["minecraft:overworld"],
+            "persistent": true
+        }"#;
+        assert_eq!(
+            parse_journeymap(json).unwrap(),
+            vec![Waypoint {
+                name: "Village".to_string(),
+                x: -312,
+                y: Some(70),
+                z: 1024,
+                color: (255, 85, 0),
+            }]
+        );
+
+        let list = r#"{"waypoints": [
+            {"name": "Off", "x": 1, "z": 2, "enable": false},
+            {"name": "Cave", "x": 5, "z": -6, "color": 65280}
+        ]}"#;
+        let waypoints = parse_journeymap(list).unwrap();
+        assert_eq!(waypoints.len(), 1);
+        assert_eq!(waypoints[0].y, None);
+        assert_eq!(waypoints[0].color, (0, 255, 0));
+    }
+}